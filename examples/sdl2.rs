@@ -61,7 +61,7 @@ fn sdl_example(video_path: &str) {
         let ptr = &mut video_subsystem as *mut _ as *mut c_void;
         let mut mpv_builder = mpv::MpvHandlerBuilder::new().expect("Error while creating MPV builder");
         mpv_builder.try_hardware_decoding();
-        let mut mpv : Box<mpv::MpvHandlerWithGl> = mpv_builder.build_with_gl(Some(get_proc_address), ptr).expect("Error while initializing MPV with opengl");
+        let mut mpv : mpv::MpvHandlerWithGl = mpv_builder.build_with_gl(Some(get_proc_address), ptr).expect("Error while initializing MPV with opengl");
         // observe the property "pause" with userdata 5.
         // When we will pause later, an event PropertyChange will be sent with userdata 5
         mpv.observe_property::<bool>("pause",5).unwrap();
@@ -79,13 +79,13 @@ fn sdl_example(video_path: &str) {
                     },
                     SdlEvent::KeyDown { keycode: Some(Keycode::Space),repeat: false, .. } => {
                         match mpv.get_property("pause").unwrap() {
-                            true => {mpv.set_property_async("pause",false,1).expect("Failed to pause player");},
-                            false => {mpv.set_property_async("pause",true,1).expect("Failed to unpause player");}
+                            true => {mpv.set_property_async_with_userdata("pause",false,1).expect("Failed to pause player");},
+                            false => {mpv.set_property_async_with_userdata("pause",true,1).expect("Failed to unpause player");}
                         }
                     },
                     // this is useless and only here to show the possibilities of the mpv-rs API
                     SdlEvent::KeyDown { keycode: Some(Keycode::O),repeat: false, .. } => {
-                        mpv.get_property_async::<&str>("speed",5).unwrap();
+                        mpv.get_property_async_with_userdata::<&str>("speed",5).unwrap();
                     },
                     _ => {}
                 }
@@ -105,6 +105,7 @@ fn sdl_example(video_path: &str) {
             let (width, height) = renderer.window().unwrap().size();
             mpv.draw(0, width as i32, -(height as i32)).expect("Failed to draw on SDL2 window");
             renderer.window().unwrap().gl_swap_window();
+            mpv.report_swap();
         }
     }else{
         error!("OpenGL driver not found, aborting");
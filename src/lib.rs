@@ -2,7 +2,7 @@
 //!
 //! Most of the documentation in this crate is based (and even copied) from
 //! [this file](https://github.com/mpv-player/mpv/blob/master/libmpv/client.h) and
-//! [this file](https://github.com/mpv-player/mpv/blob/master/libmpv/opengl_cb.h)
+//! [this file](https://github.com/mpv-player/mpv/blob/master/libmpv/render.h)
 //!
 //! # License
 //!
@@ -63,7 +63,10 @@
 //! mpv client API is available. It is also possible to integrate client API
 //! usage in other event loops (e.g. GUI toolkits) with the
 //! `mpv.set_wakeup_callback()` function, and then polling for events by calling
-//! `mpv_wait_event()` with a 0 timeout.
+//! `mpv.wait_event()` with a 0 timeout.
+//!
+//! With the `futures` feature, `mpv.event_stream()` returns a `Stream` of events instead,
+//! which can be used with any futures executor.
 //!
 //! Note that the event loop is detached from the actual player. Not calling
 //! `mpv.wait_event()` will not stop playback. It will eventually congest the
@@ -82,10 +85,13 @@
 //!
 //! The client API includes asynchronous functions. These allow you to send
 //! requests instantly, and get replies as events at a later point. The
-//! requests are made with functions carrying the _async suffix, and replies
-//! are returned by `mpv.wait_event(...)` (interleaved with the normal event stream).
+//! requests are made with functions carrying the _async suffix, which return
+//! a `Future` (an `AsyncReply`) resolved when the reply is received. Replies are received
+//! by `mpv.wait_event(...)`, so the event loop must keep running for them to be resolved.
 //!
-//! A unsigned userdata value is used to allow the user to associate requests
+//! The functions carrying the _async_with_userdata suffix return the replies
+//! with `mpv.wait_event(...)` instead (interleaved with the normal event stream).
+//! A unsigned userdata value is used to allow the user to associate these requests
 //! with replies. The value is passed as `reply_userdata` parameter to the request
 //! function. The reply to the request will have the reply
 //! `MpvEvent.reply_userdata` field set to the same value as the
@@ -145,23 +151,63 @@
 #[macro_use]
 extern crate enum_primitive;
 extern crate num;
+extern crate log;
+#[cfg(feature = "futures")]
+extern crate futures_core;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+
+#[macro_use]
+mod mpv_trace;
 
+mod mpv_async;
+mod mpv_chapter;
+mod mpv_client;
+mod mpv_command;
 mod mpv_error;
 mod mpv_enums;
 mod mpv_gen;
 mod mpv_handler;
+mod mpv_log;
+mod mpv_metadata;
+mod mpv_node;
+mod mpv_observe;
+mod mpv_playlist;
+mod mpv_render;
+mod mpv_track;
+#[cfg(feature = "futures")]
+mod mpv_stream;
 mod mpv_types;
+#[cfg(test)]
+mod mpv_command_tests;
+#[cfg(test)]
+mod mpv_tests;
+pub mod props;
 
-pub use mpv_error::{Error,Result};
+pub use mpv_async::AsyncReply;
+pub use mpv_chapter::{Chapter, Edition};
+pub use mpv_client::MpvClient;
+pub use mpv_command::{Command, LoadFileMode, SeekMode, SeekPrecision, ScreenshotMode, TrackAddMode};
+pub use mpv_error::{Error,MpvErrorCode,Result};
 pub use mpv_handler::*;
 pub use mpv_enums::{
+    MpvEventId,
     SubApi,
     LogLevel,
     EndFileReason,
     Event,
     MpvFormat,
+    MpvFormatGet,
     Format
 };
+pub use mpv_metadata::metadata_from_node;
+pub use mpv_node::Node;
+pub use mpv_observe::{ObservedProperty, MAX_PENDING_CHANGES};
+pub use mpv_playlist::{Playlist, PlaylistEntry, PlaylistObserver};
+pub use mpv_render::{RenderContext, MpvHandlerWithGl, SwFormat};
+pub use mpv_track::{Track, TrackType};
+#[cfg(feature = "futures")]
+pub use mpv_stream::EventStream;
 pub use mpv_types::* ;
 pub use mpv_gen::mpv_opengl_cb_get_proc_address_fn;
 
@@ -0,0 +1,226 @@
+use mpv_enums::{Event, Format, MpvFormatGet};
+use mpv_error::*;
+use mpv_node::Node;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// The reply userdata given to libmpv by the future-returning functions start at this value,
+/// so that they never collide with the `u32` userdata chosen by the users of this crate.
+pub const INTERNAL_USERDATA_START: u64 = 1 << 32;
+
+#[derive(Debug)]
+struct PendingReply {
+    event: Option<Event>,
+    waker: Option<Waker>,
+}
+
+///
+/// Routes the replies of asynchronous requests to the `AsyncReply` waiting for them, and
+/// the changes of observed properties to their `ObservedProperty`.
+///
+/// It is shared between a `MpvHandler`, its `AsyncReply`s and its `ObservedProperty`s, and is
+/// fed by `wait_event`.
+pub struct ReplyDispatcher {
+    next_userdata: u64,
+    pending: HashMap<u64, PendingReply>,
+    observers: HashMap<u64, Box<dyn Fn(Format) + Send>>,
+    closed: bool,
+}
+
+impl fmt::Debug for ReplyDispatcher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ReplyDispatcher")
+         .field("next_userdata", &self.next_userdata)
+         .field("pending", &self.pending)
+         .field("observers", &self.observers.keys().collect::<Vec<_>>())
+         .field("closed", &self.closed)
+         .finish()
+    }
+}
+
+impl ReplyDispatcher {
+    pub fn new() -> Arc<Mutex<ReplyDispatcher>> {
+        Arc::new(Mutex::new(ReplyDispatcher {
+            next_userdata: INTERNAL_USERDATA_START,
+            pending: HashMap::new(),
+            observers: HashMap::new(),
+            closed: false,
+        }))
+    }
+
+    fn next_userdata(&mut self) -> u64 {
+        let userdata = self.next_userdata;
+        self.next_userdata += 1;
+        userdata
+    }
+
+    /// Allocates the userdata of a new request
+    pub fn register(&mut self) -> u64 {
+        let userdata = self.next_userdata();
+        self.pending.insert(userdata, PendingReply { event: None, waker: None });
+        userdata
+    }
+
+    /// Allocates a userdata whose events are handled by the `MpvHandler` itself
+    pub fn reserve(&mut self) -> u64 {
+        self.next_userdata()
+    }
+
+    /// Allocates the userdata of a new observed property, `observer` being called
+    /// with every change of the property
+    pub fn register_observer(&mut self, observer: Box<dyn Fn(Format) + Send>) -> u64 {
+        let userdata = self.next_userdata();
+        self.observers.insert(userdata, observer);
+        userdata
+    }
+
+    /// Forgets an observed property. Returns false if the `MpvHandler` has been destroyed.
+    pub fn remove_observer(&mut self, userdata: u64) -> bool {
+        self.observers.remove(&userdata);
+        !self.closed
+    }
+
+    /// Forgets a request which could not be sent to libmpv
+    pub fn forget(&mut self, userdata: u64) {
+        self.pending.remove(&userdata);
+    }
+
+    /// Gives a reply event to the `AsyncReply` waiting for it, or a property change to
+    /// its `ObservedProperty`, if it is still alive
+    pub fn dispatch(&mut self, userdata: u64, event: Event) {
+        if let Event::PropertyChange { change, .. } = event {
+            if let Some(observer) = self.observers.get(&userdata) {
+                observer(change);
+            }
+        } else if let Some(pending) = self.pending.get_mut(&userdata) {
+            pending.event = Some(event);
+            if let Some(waker) = pending.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    /// Called when the `MpvHandler` is destroyed : no reply will ever be received anymore
+    pub fn close(&mut self) {
+        self.closed = true;
+        for pending in self.pending.values_mut() {
+            if let Some(waker) = pending.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+///
+/// A `Future` resolved when libmpv replies to an asynchronous request, such as
+/// `MpvHandler::get_property_async`.
+///
+/// Replies are received by `MpvHandler::wait_event` (or an `EventStream`), so the event loop
+/// must keep running for this future to be resolved. These replies are handled internally and
+/// are not returned by `wait_event`.
+///
+/// If the `MpvHandler` is destroyed before the reply is received, this future resolves
+/// to `MPV_ERROR_UNINITIALIZED`. Dropping this future does not cancel the request.
+#[derive(Debug)]
+pub struct AsyncReply<T> {
+    state: ReplyState<T>,
+}
+
+#[derive(Debug)]
+enum ReplyState<T> {
+    /// the request failed before being sent to libmpv
+    Failed(Option<Error>),
+    Waiting {
+        dispatcher: Arc<Mutex<ReplyDispatcher>>,
+        userdata: u64,
+        /// the name of the property or the command, for the error context
+        name: String,
+        convert: fn(Event, &str) -> Result<T>,
+    },
+}
+
+impl<T> AsyncReply<T> {
+    pub fn waiting(dispatcher: Arc<Mutex<ReplyDispatcher>>,
+                   userdata: u64,
+                   name: &str,
+                   convert: fn(Event, &str) -> Result<T>) -> AsyncReply<T> {
+        AsyncReply {
+            state: ReplyState::Waiting {
+                dispatcher,
+                userdata,
+                name: name.to_owned(),
+                convert,
+            },
+        }
+    }
+
+    pub fn failed(error: Error) -> AsyncReply<T> {
+        AsyncReply { state: ReplyState::Failed(Some(error)) }
+    }
+}
+
+impl<T> Future for AsyncReply<T> {
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<T>> {
+        match self.get_mut().state {
+            ReplyState::Failed(ref mut error) => {
+                Poll::Ready(Err(error.take().expect("AsyncReply polled after completion")))
+            },
+            ReplyState::Waiting { ref dispatcher, userdata, ref name, convert } => {
+                let mut dispatcher = dispatcher.lock().unwrap();
+                let closed = dispatcher.closed;
+                let pending = dispatcher.pending
+                                        .get_mut(&userdata)
+                                        .expect("AsyncReply polled after completion");
+                if pending.event.is_none() && !closed {
+                    pending.waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+                let event = pending.event.take();
+                dispatcher.pending.remove(&userdata);
+                Poll::Ready(match event {
+                    Some(event) => convert(event, name),
+                    None => Err(Error::Mpv(MpvErrorCode::MPV_ERROR_UNINITIALIZED)),
+                })
+            },
+        }
+    }
+}
+
+impl<T> Drop for AsyncReply<T> {
+    fn drop(&mut self) {
+        if let ReplyState::Waiting { ref dispatcher, userdata, .. } = self.state {
+            // the reply will be ignored if it is received later
+            if let Ok(mut dispatcher) = dispatcher.lock() {
+                dispatcher.pending.remove(&userdata);
+            }
+        }
+    }
+}
+
+pub fn get_property_reply<T: MpvFormatGet>(event: Event, name: &str) -> Result<T> {
+    match event {
+        Event::GetPropertyReply { result, .. } => result.and_then(T::from_format),
+        _ => unreachable!("unexpected reply to mpv_get_property_async: {:?}", event),
+    }.property_context(name)
+}
+
+pub fn set_property_reply(event: Event, name: &str) -> Result<()> {
+    match event {
+        Event::SetPropertyReply(result, _) => result,
+        _ => unreachable!("unexpected reply to mpv_set_property_async: {:?}", event),
+    }.property_context(name)
+}
+
+pub fn command_reply(event: Event, name: &str) -> Result<Node> {
+    match event {
+        Event::CommandReply(result, _) => result,
+        _ => unreachable!("unexpected reply to mpv_command_async: {:?}", event),
+    }.command_context(name)
+}
@@ -0,0 +1,89 @@
+use mpv_handler::MpvHandler;
+use mpv_node::{Node, list_from_node};
+use mpv_error::*;
+
+/// A chapter of the current file, read from the `chapter-list` property
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chapter {
+    /// The title of the chapter, if the file gives one
+    pub title: Option<String>,
+    /// The start of the chapter, in seconds
+    pub time: f64,
+}
+
+impl Chapter {
+    /// Reads a chapter from one element of the `chapter-list` property
+    pub fn from_node(node: &Node) -> Result<Chapter> {
+        let time = node.required("time", Node::as_f64)?;
+        Ok(Chapter {
+            title: node.get("title").and_then(Node::as_str).map(|title| title.to_owned()),
+            time,
+        })
+    }
+}
+
+/// An edition of the current file, read from the `edition-list` property.
+///
+/// Editions are alternative versions of the same file, used by some Matroska files.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edition {
+    /// The id of the edition, which is also its index
+    pub id: i64,
+    /// The title of the edition, if the file gives one
+    pub title: Option<String>,
+    /// Whether this edition is the default one
+    pub default: bool,
+}
+
+impl Edition {
+    /// Reads an edition from one element of the `edition-list` property
+    pub fn from_node(node: &Node) -> Result<Edition> {
+        let id = node.required("id", Node::as_i64)?;
+        Ok(Edition {
+            id,
+            title: node.get("title").and_then(Node::as_str).map(|title| title.to_owned()),
+            default: node.get("default").and_then(Node::as_bool).unwrap_or(false),
+        })
+    }
+}
+
+impl MpvHandler {
+    /// Returns the chapters of the current file, sorted by time
+    pub fn chapters(&self) -> Result<Vec<Chapter>> {
+        let node: Node = self.get_property("chapter-list")?;
+        list_from_node(&node, Chapter::from_node).property_context("chapter-list")
+    }
+
+    /// Returns the editions of the current file
+    pub fn editions(&self) -> Result<Vec<Edition>> {
+        let node: Node = self.get_property("edition-list")?;
+        list_from_node(&node, Edition::from_node).property_context("edition-list")
+    }
+
+    /// Returns the index of the current chapter, or `None` if the current position is
+    /// before the first chapter or if the file has no chapters
+    pub fn current_chapter(&self) -> Result<Option<usize>> {
+        match self.get_property::<i64>("chapter") {
+            Ok(chapter) if chapter >= 0 => Ok(Some(chapter as usize)),
+            Ok(_) => Ok(None),
+            Err(ref e) if matches!(e.mpv_code(),
+                                   Some(MpvErrorCode::MPV_ERROR_PROPERTY_UNAVAILABLE)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Seek to the start of the chapter at `index`
+    pub fn set_chapter(&mut self, index: usize) -> Result<()> {
+        self.set_property("chapter", index as i64)
+    }
+
+    /// Seek to the start of the next chapter
+    pub fn next_chapter(&mut self) -> Result<()> {
+        self.command(&["add", "chapter", "1"])
+    }
+
+    /// Seek to the start of the previous chapter
+    pub fn previous_chapter(&mut self) -> Result<()> {
+        self.command(&["add", "chapter", "-1"])
+    }
+}
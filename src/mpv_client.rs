@@ -0,0 +1,70 @@
+use mpv_gen::{mpv_create_client, mpv_client_name};
+use mpv_handler::MpvHandler;
+use mpv_error::*;
+
+use std::ffi::CStr;
+use std::ops::{Deref, DerefMut};
+
+///
+/// An additional client of the player of a `MpvHandler`, created with
+/// `MpvHandler::create_client(...)`.
+///
+/// This struct is a decorator of `MpvHandler`, and can use all the functions from `MpvHandler`.
+/// It controls the same player, but has its own event queue, its own observed properties and
+/// its own wakeup callback, so that several parts of an application can receive events
+/// independently.
+///
+/// Dropping a client only detaches it, the player keeps running : see `Ownership`.
+///
+/// # Example
+/// ```no_run
+/// # let mpv = mpv::MpvHandlerBuilder::new().unwrap().build().unwrap();
+/// let mut telemetry = mpv.create_client("telemetry").expect("Failed to create a client");
+/// std::thread::spawn(move || {
+///     while let Some(event) = telemetry.wait_event(-1.0) {
+///         println!("{} : {:?}", telemetry.client_name(), event);
+///         // the player waits for every client to be dropped before shutting down
+///         if let mpv::Event::Shutdown = event {
+///             break;
+///         }
+///     }
+/// });
+/// ```
+#[derive(Debug)]
+pub struct MpvClient {
+    mpv_handler: MpvHandler,
+}
+
+impl MpvHandler {
+    ///
+    /// Create a new client of this player, named `name`. The name is used by mpv in its
+    /// log messages, and by the `script-message-to` command to send messages to the client.
+    /// mpv makes it unique by appending a number if a client already has this name.
+    pub fn create_client(&self, name: &str) -> Result<MpvClient> {
+        let c_name = to_cstring(name)?;
+        let handle = unsafe { mpv_create_client(self.raw(), c_name.as_ptr()) };
+        if handle.is_null() {
+            return Err(Error::Mpv(MpvErrorCode::MPV_ERROR_NOMEM));
+        }
+        Ok(MpvClient { mpv_handler: MpvHandler::from_client_handle(handle) })
+    }
+
+    /// Returns the name of this client, "main" for the `MpvHandler` created by the builder
+    pub fn client_name(&self) -> String {
+        let name = unsafe { mpv_client_name(self.raw()) };
+        unsafe { CStr::from_ptr(name).to_string_lossy().into_owned() }
+    }
+}
+
+impl Deref for MpvClient {
+    type Target = MpvHandler;
+    fn deref(&self) -> &MpvHandler {
+        &self.mpv_handler
+    }
+}
+
+impl DerefMut for MpvClient {
+    fn deref_mut(&mut self) -> &mut MpvHandler {
+        &mut self.mpv_handler
+    }
+}
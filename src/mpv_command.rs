@@ -0,0 +1,350 @@
+use mpv_handler::MpvHandler;
+use mpv_async::AsyncReply;
+use mpv_node::Node;
+use mpv_error::Result;
+
+use std::collections::BTreeMap;
+
+/// How `Command::LoadFile` changes the playlist
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadFileMode {
+    /// Stop playback of the current file, and play the new file immediately
+    Replace,
+    /// Append the file to the playlist
+    Append,
+    /// Append the file, and if nothing is currently playing, start playback
+    AppendPlay,
+    /// Insert the file in the playlist at the given index. Requires mpv 0.38 or later.
+    InsertAt(usize),
+}
+
+impl LoadFileMode {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            LoadFileMode::Replace => "replace",
+            LoadFileMode::Append => "append",
+            LoadFileMode::AppendPlay => "append-play",
+            LoadFileMode::InsertAt(_) => "insert-at",
+        }
+    }
+}
+
+/// How the target of `Command::Seek` is interpreted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SeekMode {
+    /// Seek relative to the current position, in seconds (a negative value seeks backwards)
+    Relative,
+    /// Seek to a given time, in seconds (a negative value seeks from the end of the file)
+    Absolute,
+    /// Seek relative to the current position, in percent of the file
+    RelativePercent,
+    /// Seek to a given position, in percent of the file
+    AbsolutePercent,
+}
+
+impl SeekMode {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            SeekMode::Relative => "relative",
+            SeekMode::Absolute => "absolute",
+            SeekMode::RelativePercent => "relative-percent",
+            SeekMode::AbsolutePercent => "absolute-percent",
+        }
+    }
+}
+
+/// The precision of `Command::Seek`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SeekPrecision {
+    /// Use the `hr-seek` option of mpv
+    Default,
+    /// Seek to the nearest keyframe, which is fast
+    Keyframes,
+    /// Seek to the exact position, which is slower
+    Exact,
+}
+
+/// What `Command::ScreenshotToFile` captures
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScreenshotMode {
+    /// The video with the subtitles and the OSD
+    Subtitles,
+    /// The video only
+    Video,
+    /// The content of the mpv window, as it is displayed
+    Window,
+}
+
+impl ScreenshotMode {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            ScreenshotMode::Subtitles => "subtitles",
+            ScreenshotMode::Video => "video",
+            ScreenshotMode::Window => "window",
+        }
+    }
+}
+
+/// How `Command::SubAdd` and `Command::AudioAdd` select the added track
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrackAddMode {
+    /// Select the added track immediately
+    Select,
+    /// Add the track without selecting it
+    Auto,
+    /// Select the track if it has already been added, otherwise add and select it
+    Cached,
+}
+
+impl TrackAddMode {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            TrackAddMode::Select => "select",
+            TrackAddMode::Auto => "auto",
+            TrackAddMode::Cached => "cached",
+        }
+    }
+}
+
+///
+/// The most common input commands of mpv, with typed arguments.
+///
+/// A `Command` can be sent with `MpvHandler::run` or `MpvHandler::run_async`, or converted
+/// into the arguments expected by `MpvHandler::command` with `args()` and into the `Node`
+/// expected by `MpvHandler::command_node` with `to_node()`.
+///
+/// See http://mpv.io/manual/master/#list-of-input-commands for the complete list of commands.
+///
+/// # Example
+/// ```no_run
+/// use mpv::{Command, LoadFileMode, SeekMode, SeekPrecision};
+///
+/// # let mut mpv = mpv::MpvHandlerBuilder::new().unwrap().build().unwrap();
+/// mpv.run(&Command::load_file("video.mkv", LoadFileMode::Replace)).expect("Failed to load");
+/// mpv.run(&Command::Seek {
+///     target: 30.0,
+///     mode: SeekMode::Absolute,
+///     precision: SeekPrecision::Exact,
+/// }).expect("Failed to seek");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// `loadfile` : load a file or an URL
+    LoadFile {
+        path: String,
+        mode: LoadFileMode,
+        /// Options set while this file is playing, such as `("start", "30")`
+        options: Vec<(String, String)>,
+    },
+    /// `seek` : change the position in the current file
+    Seek {
+        target: f64,
+        mode: SeekMode,
+        precision: SeekPrecision,
+    },
+    /// `screenshot-to-file` : save a screenshot to the given file, the format
+    /// being deduced from the extension
+    ScreenshotToFile {
+        path: String,
+        mode: ScreenshotMode,
+    },
+    /// `playlist-next` : go to the next entry of the playlist. If `force` is false,
+    /// nothing happens at the end of the playlist, otherwise playback stops.
+    PlaylistNext { force: bool },
+    /// `playlist-prev` : go to the previous entry of the playlist. If `force` is false,
+    /// nothing happens at the start of the playlist, otherwise playback stops.
+    PlaylistPrev { force: bool },
+    /// `sub-add` : load a subtitle file
+    SubAdd {
+        path: String,
+        mode: TrackAddMode,
+        title: Option<String>,
+        lang: Option<String>,
+    },
+    /// `audio-add` : load an audio file as an additional audio track
+    AudioAdd {
+        path: String,
+        mode: TrackAddMode,
+        title: Option<String>,
+        lang: Option<String>,
+    },
+    /// `cycle` : cycle the value of a property, upwards or downwards
+    Cycle { property: String, up: bool },
+    /// `add` : add `value` to a numeric property (a negative value substracts it)
+    Add { property: String, value: f64 },
+    /// `set` : set a property, the value being parsed by mpv
+    Set { property: String, value: String },
+    /// `show-text` : display a text on the OSD, for `duration_ms` milliseconds or the
+    /// duration given by the `osd-duration` option
+    ShowText { text: String, duration_ms: Option<i64> },
+    /// `stop` : stop playback and clear the playlist
+    Stop,
+    /// `quit` : exit the player, with the given exit code if any
+    Quit { code: Option<i32> },
+}
+
+impl Command {
+    /// Shortcut for a `Command::LoadFile` without options
+    pub fn load_file(path: &str, mode: LoadFileMode) -> Command {
+        Command::LoadFile {
+            path: path.to_owned(),
+            mode,
+            options: Vec::new(),
+        }
+    }
+
+    /// Returns the name of the command in mpv, such as "loadfile"
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Command::LoadFile { .. } => "loadfile",
+            Command::Seek { .. } => "seek",
+            Command::ScreenshotToFile { .. } => "screenshot-to-file",
+            Command::PlaylistNext { .. } => "playlist-next",
+            Command::PlaylistPrev { .. } => "playlist-prev",
+            Command::SubAdd { .. } => "sub-add",
+            Command::AudioAdd { .. } => "audio-add",
+            Command::Cycle { .. } => "cycle",
+            Command::Add { .. } => "add",
+            Command::Set { .. } => "set",
+            Command::ShowText { .. } => "show-text",
+            Command::Stop => "stop",
+            Command::Quit { .. } => "quit",
+        }
+    }
+
+    /// Returns the named arguments of the command, without its name
+    fn named_args(&self) -> Vec<(&'static str, Node)> {
+        match *self {
+            Command::LoadFile { ref path, mode, ref options } => {
+                let mut args = vec![("url", Node::from(path.as_str())),
+                                    ("flags", Node::from(mode.as_str()))];
+                if let LoadFileMode::InsertAt(index) = mode {
+                    args.push(("index", Node::from(index as i64)));
+                }
+                if !options.is_empty() {
+                    let options = options.iter()
+                                         .map(|(k, v)| (k.clone(), Node::from(v.as_str())))
+                                         .collect::<BTreeMap<_, _>>();
+                    args.push(("options", Node::Map(options)));
+                }
+                args
+            },
+            Command::Seek { target, mode, precision } => {
+                let flags = match precision {
+                    SeekPrecision::Default => mode.as_str().to_owned(),
+                    SeekPrecision::Keyframes => format!("{}+keyframes", mode.as_str()),
+                    SeekPrecision::Exact => format!("{}+exact", mode.as_str()),
+                };
+                vec![("target", Node::from(target)), ("flags", Node::from(flags))]
+            },
+            Command::ScreenshotToFile { ref path, mode } => {
+                vec![("filename", Node::from(path.as_str())), ("flags", Node::from(mode.as_str()))]
+            },
+            Command::PlaylistNext { force } |
+            Command::PlaylistPrev { force } => {
+                vec![("flags", Node::from(if force { "force" } else { "weak" }))]
+            },
+            Command::SubAdd { ref path, mode, ref title, ref lang } |
+            Command::AudioAdd { ref path, mode, ref title, ref lang } => {
+                let mut args = vec![("url", Node::from(path.as_str())),
+                                    ("flags", Node::from(mode.as_str()))];
+                // lang is positional after title, so title must be given if lang is
+                if title.is_some() || lang.is_some() {
+                    args.push(("title", Node::from(title.clone().unwrap_or_default())));
+                }
+                if let Some(ref lang) = *lang {
+                    args.push(("lang", Node::from(lang.as_str())));
+                }
+                args
+            },
+            Command::Cycle { ref property, up } => {
+                vec![("name", Node::from(property.as_str())),
+                     ("value", Node::from(if up { "up" } else { "down" }))]
+            },
+            Command::Add { ref property, value } => {
+                vec![("name", Node::from(property.as_str())), ("value", Node::from(value))]
+            },
+            Command::Set { ref property, ref value } => {
+                vec![("name", Node::from(property.as_str())), ("value", Node::from(value.as_str()))]
+            },
+            Command::ShowText { ref text, duration_ms } => {
+                let mut args = vec![("text", Node::from(text.as_str()))];
+                if let Some(duration_ms) = duration_ms {
+                    args.push(("duration", Node::from(duration_ms)));
+                }
+                args
+            },
+            Command::Stop => Vec::new(),
+            Command::Quit { code } => {
+                code.map(|code| vec![("code", Node::from(code as i64))]).unwrap_or_default()
+            },
+        }
+    }
+
+    ///
+    /// Returns the arguments of the command, the first one being its name, as expected
+    /// by `MpvHandler::command`.
+    ///
+    /// The per-file options of `Command::LoadFile` are given after the index argument
+    /// (-1 unless inserting) added by mpv 0.38, so older versions reject them : use
+    /// `to_node()` instead with these versions.
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec![self.name().to_owned()];
+        let mut has_index = false;
+        for (key, value) in self.named_args() {
+            has_index |= key == "index";
+            match value {
+                Node::String(s) => args.push(s),
+                Node::Double(d) => args.push(d.to_string()),
+                Node::Int64(i) => args.push(i.to_string()),
+                Node::Map(options) => {
+                    if !has_index {
+                        args.push("-1".to_owned());
+                    }
+                    let options = options.into_iter()
+                                         .map(|(k, v)| {
+                                             format!("{}={}", k, quote_option(v.as_str().unwrap_or("")))
+                                         })
+                                         .collect::<Vec<_>>();
+                    args.push(options.join(","));
+                },
+                _ => unreachable!("unexpected command argument {:?}", value),
+            }
+        }
+        args
+    }
+
+    /// Returns the command as a `Node::Map` of named arguments, as expected
+    /// by `MpvHandler::command_node`.
+    pub fn to_node(&self) -> Node {
+        let mut map = BTreeMap::new();
+        map.insert("name".to_owned(), Node::from(self.name()));
+        for (key, value) in self.named_args() {
+            map.insert(key.to_owned(), value);
+        }
+        Node::Map(map)
+    }
+}
+
+/// Quotes the value of an option with the `%length%value` syntax of mpv, unless it only
+/// has characters which cannot be mistaken for a separator or a quote
+pub(crate) fn quote_option(value: &str) -> String {
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || "-_.:/+".contains(c)) {
+        value.to_owned()
+    } else {
+        format!("%{}%{}", value.len(), value)
+    }
+}
+
+impl MpvHandler {
+    /// Run a `Command` synchronously, returning its result
+    pub fn run(&mut self, command: &Command) -> Result<Node> {
+        self.command_node(&command.to_node())
+    }
+
+    /// Run a `Command` asynchronously, the returned future being resolved
+    /// with its result
+    pub fn run_async(&mut self, command: &Command) -> AsyncReply<Node> {
+        self.command_node_async(&command.to_node())
+    }
+}
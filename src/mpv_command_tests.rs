@@ -0,0 +1,107 @@
+// Tests of the serialization of `Command`, which does not need libmpv
+
+use mpv_command::{Command, LoadFileMode, SeekMode, SeekPrecision, TrackAddMode, quote_option};
+use mpv_node::Node;
+
+use std::collections::BTreeMap;
+
+fn map(entries: Vec<(&str, Node)>) -> Node {
+    Node::Map(entries.into_iter().map(|(key, value)| (key.to_owned(), value)).collect())
+}
+
+#[test]
+fn load_file_args_give_the_index_before_the_options() {
+    let command = Command::LoadFile {
+        path: "video.mkv".to_owned(),
+        mode: LoadFileMode::Append,
+        options: vec![("start".to_owned(), "30".to_owned()),
+                      ("title".to_owned(), "a,b=c".to_owned())],
+    };
+    assert_eq!(command.args(),
+               vec!["loadfile", "video.mkv", "append", "-1", "start=30,title=%5%a,b=c"]);
+}
+
+#[test]
+fn load_file_without_options_has_no_index() {
+    let command = Command::load_file("video.mkv", LoadFileMode::Replace);
+    assert_eq!(command.args(), vec!["loadfile", "video.mkv", "replace"]);
+}
+
+#[test]
+fn load_file_insert_at_gives_its_index() {
+    let command = Command::LoadFile {
+        path: "video.mkv".to_owned(),
+        mode: LoadFileMode::InsertAt(2),
+        options: vec![("start".to_owned(), "30".to_owned())],
+    };
+    assert_eq!(command.args(), vec!["loadfile", "video.mkv", "insert-at", "2", "start=30"]);
+    assert_eq!(command.to_node().get("index"), Some(&Node::from(2i64)));
+}
+
+#[test]
+fn load_file_node_keeps_the_options_unquoted() {
+    let command = Command::LoadFile {
+        path: "video.mkv".to_owned(),
+        mode: LoadFileMode::AppendPlay,
+        options: vec![("title".to_owned(), "a,b=c".to_owned())],
+    };
+    let mut options = BTreeMap::new();
+    options.insert("title".to_owned(), Node::from("a,b=c"));
+    assert_eq!(command.to_node(), map(vec![("name", Node::from("loadfile")),
+                                           ("url", Node::from("video.mkv")),
+                                           ("flags", Node::from("append-play")),
+                                           ("options", Node::Map(options))]));
+}
+
+#[test]
+fn seek_flags_combine_mode_and_precision() {
+    let seek = |mode, precision| Command::Seek { target: 30.0, mode, precision }.args();
+    assert_eq!(seek(SeekMode::Relative, SeekPrecision::Default), vec!["seek", "30", "relative"]);
+    assert_eq!(seek(SeekMode::Absolute, SeekPrecision::Exact),
+               vec!["seek", "30", "absolute+exact"]);
+    assert_eq!(seek(SeekMode::AbsolutePercent, SeekPrecision::Keyframes),
+               vec!["seek", "30", "absolute-percent+keyframes"]);
+}
+
+#[test]
+fn seek_node_keeps_the_target_a_double() {
+    let command = Command::Seek {
+        target: -2.5,
+        mode: SeekMode::RelativePercent,
+        precision: SeekPrecision::Default,
+    };
+    assert_eq!(command.to_node(), map(vec![("name", Node::from("seek")),
+                                           ("target", Node::from(-2.5)),
+                                           ("flags", Node::from("relative-percent"))]));
+}
+
+#[test]
+fn sub_add_with_lang_only_gives_an_empty_title() {
+    let command = Command::SubAdd {
+        path: "subs.srt".to_owned(),
+        mode: TrackAddMode::Auto,
+        title: None,
+        lang: Some("en".to_owned()),
+    };
+    assert_eq!(command.args(), vec!["sub-add", "subs.srt", "auto", "", "en"]);
+    assert_eq!(command.to_node(), map(vec![("name", Node::from("sub-add")),
+                                           ("url", Node::from("subs.srt")),
+                                           ("flags", Node::from("auto")),
+                                           ("title", Node::from("")),
+                                           ("lang", Node::from("en"))]));
+}
+
+#[test]
+fn quote_option_leaves_plain_values() {
+    assert_eq!(quote_option("30"), "30");
+    assert_eq!(quote_option("http://example.com/a-b_c.mkv"), "http://example.com/a-b_c.mkv");
+}
+
+#[test]
+fn quote_option_prefixes_the_length_in_bytes() {
+    assert_eq!(quote_option("a,b"), "%3%a,b");
+    assert_eq!(quote_option("a=b"), "%3%a=b");
+    assert_eq!(quote_option("a b"), "%3%a b");
+    assert_eq!(quote_option("été"), "%5%été");
+    assert_eq!(quote_option(""), "%0%");
+}
@@ -1,11 +1,12 @@
-use std::{ffi, fmt, ptr};
+use std::{ffi, fmt, ptr, slice};
 use std::ffi::CStr;
-use std::mem;
+use std::collections::BTreeMap;
 
 use mpv_error::* ;
 use mpv_types::OsdString;
+use mpv_node::{Node, MpvNodeStorage};
 use mpv_gen::{mpv_event_name,MpvFormat as MpvInternalFormat,mpv_event_property,mpv_event_end_file,
-    mpv_event_log_message,mpv_free};
+    mpv_event_log_message,mpv_event_command,mpv_event_client_message,mpv_free,mpv_node,mpv_node_list,mpv_byte_array,mpv_free_node_contents};
 pub use mpv_gen::{MpvEventId, SubApi, LogLevel, EndFileReason};
 use ::std::os::raw::{c_int,c_void,c_ulong,c_char};
 
@@ -23,18 +24,26 @@ impl fmt::Display for MpvEventId {
     }
 }
 
+///
+/// An event sent by mpv, returned by `MpvHandler::wait_event`.
+///
+/// Every payload is copied out of the libmpv memory, which means an `Event` can be kept
+/// after the next call to `wait_event`.
 #[derive(Debug)]
-pub enum Event<'a> {
+pub enum Event {
     /// Received when the player is shutting down
     Shutdown,
-    /// *Has not been tested*, received when explicitly asked to MPV
-    LogMessage{prefix:&'static str,level:&'static str,text:&'static str,log_level:LogLevel},
+    /// Received after a call to `MpvHandler::request_log_messages`. `text` ends with a newline.
+    LogMessage{prefix:String,level:String,text:String,log_level:LogLevel},
     /// Received when using get_property_async
-    GetPropertyReply{name:&'static str,result:Result<Format<'a>>,reply_userdata:u32},
+    GetPropertyReply{name:String,result:Result<Format>,reply_userdata:u32},
     /// Received when using set_property_async
     SetPropertyReply(Result<()>,u32),
-    /// Received when using command_async
-    CommandReply(Result<()>,u32),
+    /// Received when using command_async or command_node_async.
+    ///
+    /// The `Node` is the result of the command, and is `Node::None` for most commands
+    /// or when libmpv is too old to send it.
+    CommandReply(Result<Node>,u32),
     /// Event received when a new file is playing
     StartFile,
     /// Event received when the file being played currently has stopped, for an error or not
@@ -51,16 +60,22 @@ pub enum Event<'a> {
     /// The player started playback again
     Unpause,
     Tick,
-    /// **Unimplemented**
-    ClientMessage,
+    /// Received when another client (a Lua script for instance) sends a message to this client,
+    /// typically with the `script-message` or `script-message-to` commands
+    ClientMessage(Vec<String>),
     VideoReconfig,
     AudioReconfig,
-    MetadataUpdate,
+    /// Received when the tags of the current file change, such as the ICY title of a stream.
+    ///
+    /// The tags are only given after a call to `MpvHandler::observe_metadata`, and are then
+    /// `None` when nothing is playing. Without it, the event is sent by libmpv without tags,
+    /// and is deprecated since libmpv 1.25.
+    MetadataUpdate(Option<BTreeMap<String, String>>),
     /// The player changed current position
     Seek,
     PlaybackRestart,
     /// Received when used with observe_property
-    PropertyChange{name:&'static str,change:Format<'a>,reply_userdata:u32},
+    PropertyChange{name:String,change:Format,reply_userdata:u32},
     ChapterChange,
     /// Received when the Event Queue is full
     QueueOverflow,
@@ -68,19 +83,19 @@ pub enum Event<'a> {
     Unused
 }
 
-pub fn to_event<'a>(event_id:MpvEventId,
+pub fn to_event(event_id:MpvEventId,
                 error: c_int,
                 reply_userdata: c_ulong,
-                data:*mut c_void) -> Option<Event<'a>> {
+                data:*mut c_void) -> Option<Event> {
     let userdata = reply_userdata as u32 ;
     match event_id {
         MpvEventId::MPV_EVENT_NONE                  => None,
         MpvEventId::MPV_EVENT_SHUTDOWN              => Some(Event::Shutdown),
         MpvEventId::MPV_EVENT_LOG_MESSAGE           => {
             let log_message = unsafe {*(data as *mut mpv_event_log_message)};
-            let prefix = unsafe { CStr::from_ptr(log_message.prefix).to_str().unwrap() };
-            let level  = unsafe { CStr::from_ptr(log_message.level ).to_str().unwrap() };
-            let text   = unsafe { CStr::from_ptr(log_message.text  ).to_str().unwrap() };
+            let prefix = unsafe { CStr::from_ptr(log_message.prefix).to_string_lossy().into_owned() };
+            let level  = unsafe { CStr::from_ptr(log_message.level ).to_string_lossy().into_owned() };
+            let text   = unsafe { CStr::from_ptr(log_message.text  ).to_string_lossy().into_owned() };
             Some(Event::LogMessage{prefix:prefix,level:level,text:text,log_level:log_message.log_level})
         },
         MpvEventId::MPV_EVENT_GET_PROPERTY_REPLY    => {
@@ -88,21 +103,30 @@ pub fn to_event<'a>(event_id:MpvEventId,
             let format_result = Format::get_from_c_void(property_struct.format,property_struct.data);
             let string = unsafe {
                 CStr::from_ptr(property_struct.name)
-                 .to_str()
-                 .unwrap()
+                 .to_string_lossy()
+                 .into_owned()
             };
             let result = ret_to_result(error, format_result);
             Some(Event::GetPropertyReply{name:string,result:result,reply_userdata:userdata})
         },
         MpvEventId::MPV_EVENT_SET_PROPERTY_REPLY    => Some(Event::SetPropertyReply(ret_to_result(error,()), userdata)),
-        MpvEventId::MPV_EVENT_COMMAND_REPLY         => Some(Event::CommandReply(ret_to_result(error,()), userdata)),
+        MpvEventId::MPV_EVENT_COMMAND_REPLY         => {
+            // data is only set since libmpv 1.102
+            let node = if data.is_null() {
+                Node::None
+            } else {
+                let command = data as *const mpv_event_command;
+                unsafe { Node::from_mpv_node(&(*command).result) }
+            };
+            Some(Event::CommandReply(ret_to_result(error, node), userdata))
+        },
         MpvEventId::MPV_EVENT_START_FILE            => Some(Event::StartFile),
         MpvEventId::MPV_EVENT_END_FILE              => {
             let end_file = unsafe {*(data as *mut mpv_event_end_file)};
-            let end_file_reason = EndFileReason::from_i32(end_file.reason).unwrap();
-            let result = match end_file_reason {
-                EndFileReason::MPV_END_FILE_REASON_ERROR => Err(Error::from_i32(end_file.error).unwrap()),
-                _ => Ok(end_file_reason)
+            let result = match EndFileReason::from_i32(end_file.reason) {
+                Some(EndFileReason::MPV_END_FILE_REASON_ERROR) => Err(error_from_code(end_file.error)),
+                Some(end_file_reason) => Ok(end_file_reason),
+                None => Err(Error::UnknownEndFileReason(end_file.reason))
             };
             Some(Event::EndFile(result))
         }
@@ -114,10 +138,23 @@ pub fn to_event<'a>(event_id:MpvEventId,
         MpvEventId::MPV_EVENT_UNPAUSE               => Some(Event::Unpause),
         MpvEventId::MPV_EVENT_TICK                  => Some(Event::Tick),
         MpvEventId::MPV_EVENT_SCRIPT_INPUT_DISPATCH => Some(Event::Unused),
-        MpvEventId::MPV_EVENT_CLIENT_MESSAGE        => unimplemented!(),
+        MpvEventId::MPV_EVENT_CLIENT_MESSAGE        => {
+            let client_message = unsafe {*(data as *mut mpv_event_client_message)};
+            let args = if client_message.num_args <= 0 || client_message.args.is_null() {
+                Vec::new()
+            } else {
+                let args = unsafe {
+                    slice::from_raw_parts(client_message.args, client_message.num_args as usize)
+                };
+                args.iter()
+                    .map(|arg| unsafe { CStr::from_ptr(*arg).to_string_lossy().into_owned() })
+                    .collect()
+            };
+            Some(Event::ClientMessage(args))
+        },
         MpvEventId::MPV_EVENT_VIDEO_RECONFIG        => Some(Event::VideoReconfig),
         MpvEventId::MPV_EVENT_AUDIO_RECONFIG        => Some(Event::AudioReconfig),
-        MpvEventId::MPV_EVENT_METADATA_UPDATE       => Some(Event::MetadataUpdate),
+        MpvEventId::MPV_EVENT_METADATA_UPDATE       => Some(Event::MetadataUpdate(None)),
         MpvEventId::MPV_EVENT_SEEK                  => Some(Event::Seek),
         MpvEventId::MPV_EVENT_PLAYBACK_RESTART      => Some(Event::PlaybackRestart),
         MpvEventId::MPV_EVENT_PROPERTY_CHANGE       => {
@@ -125,8 +162,8 @@ pub fn to_event<'a>(event_id:MpvEventId,
             let format_result = Format::get_from_c_void(property_struct.format,property_struct.data);
             let name = unsafe {
                 CStr::from_ptr(property_struct.name)
-                 .to_str()
-                 .unwrap()
+                 .to_string_lossy()
+                 .into_owned()
             };
             Some(Event::PropertyChange{name:name,change:format_result,reply_userdata:userdata})
         },
@@ -137,36 +174,53 @@ pub fn to_event<'a>(event_id:MpvEventId,
 
 ///
 /// Event replies `GetPropertyReply` and `PropertyChange` will answer this object.
-///
-/// This list is incomplete, the current formats are missing :
-///
-/// * `Node`
-/// * `NodeArray`
-/// * `NodeMap`
-/// * `ByteArray`
-
 #[derive(Debug)]
-pub enum Format<'a>{
+pub enum Format{
+    /// The property is not available
+    None,
     Flag(bool),
-    Str(&'a str),
+    Str(String),
     Double(f64),
     Int(i64),
-    OsdStr(&'a str)
+    OsdStr(String),
+    /// Sent when the property has been requested or observed as a `Node`
+    Node(Node),
+    ByteArray(Vec<u8>)
 }
 
-impl<'a> Format<'a> {
+impl Format {
+    /// Returns a human readable name of the kind of this value, such as "string" or "flag"
+    pub fn format_name(&self) -> &'static str {
+        match *self {
+            Format::None => "none",
+            Format::Flag(_) => "flag",
+            Format::Str(_) => "string",
+            Format::Double(_) => "double",
+            Format::Int(_) => "int64",
+            Format::OsdStr(_) => "OSD string",
+            Format::Node(_) => "node",
+            Format::ByteArray(_) => "byte array",
+        }
+    }
+
     pub fn get_mpv_format(&self) -> MpvInternalFormat {
         match *self {
+            Format::None => MpvInternalFormat::MPV_FORMAT_NONE,
             Format::Flag(_) => MpvInternalFormat::MPV_FORMAT_FLAG,
             Format::Str(_) => MpvInternalFormat::MPV_FORMAT_STRING,
             Format::Double(_) => MpvInternalFormat::MPV_FORMAT_DOUBLE,
             Format::Int(_) => MpvInternalFormat::MPV_FORMAT_INT64,
             Format::OsdStr(_) => MpvInternalFormat::MPV_FORMAT_OSD_STRING,
+            Format::Node(_) => MpvInternalFormat::MPV_FORMAT_NODE,
+            Format::ByteArray(_) => MpvInternalFormat::MPV_FORMAT_BYTE_ARRAY,
         }
     }
     ///
     /// This is used internally by the mpv-rs crate, you probably should not be using this.
     ///
+    /// The data pointed to is owned by libmpv and is only valid until the next call
+    /// to `mpv_wait_event`, so everything is copied and nothing is freed here.
+    /// Invalid UTF-8 sequences in strings are replaced by `U+FFFD`.
     pub fn get_from_c_void(format:MpvInternalFormat,pointer:*mut c_void) -> Self {
         match format {
             MpvInternalFormat::MPV_FORMAT_FLAG => {
@@ -176,19 +230,17 @@ impl<'a> Format<'a> {
                 let char_ptr = unsafe {*(pointer as *mut *mut c_char)};
                 Format::Str(unsafe {
                     CStr::from_ptr(char_ptr)
-                         .to_str()
-                         .unwrap()
+                         .to_string_lossy()
+                         .into_owned()
                 })
-                // TODO : mpv_free
             },
             MpvInternalFormat::MPV_FORMAT_OSD_STRING => {
                 let char_ptr = unsafe{ *(pointer as *mut *mut c_char)};
                 Format::OsdStr(unsafe {
                     CStr::from_ptr(char_ptr)
-                         .to_str()
-                         .unwrap()
+                         .to_string_lossy()
+                         .into_owned()
                 })
-                // TODO : mpv_free
             },
             MpvInternalFormat::MPV_FORMAT_DOUBLE => {
                 Format::Double(unsafe { *(pointer as *mut f64) })
@@ -196,8 +248,19 @@ impl<'a> Format<'a> {
             MpvInternalFormat::MPV_FORMAT_INT64 => {
                 Format::Int(unsafe { *(pointer as *mut i64) })
             },
+            MpvInternalFormat::MPV_FORMAT_NODE => {
+                Format::Node(unsafe { Node::from_mpv_node(pointer as *const mpv_node) })
+            },
+            MpvInternalFormat::MPV_FORMAT_NODE_ARRAY |
+            MpvInternalFormat::MPV_FORMAT_NODE_MAP => {
+                Format::Node(unsafe { Node::from_mpv_node_list(pointer as *const mpv_node_list, format) })
+            },
+            MpvInternalFormat::MPV_FORMAT_BYTE_ARRAY => {
+                let byte_array = pointer as *const mpv_byte_array;
+                Format::ByteArray(unsafe { Node::bytes_from_mpv_byte_array(byte_array) })
+            },
             _ => {
-                Format::Flag(false)
+                Format::None
             }
         }
     }
@@ -212,109 +275,182 @@ impl<'a> Format<'a> {
 /// * `MPV_FORMAT_DOUBLE` : `f64`
 /// * `MPV_FORMAT_INT64` : `i64`
 /// * `MPV_FORMAT_OSD_STRING` : [`OsdString`](struct.OsdString.html)
-/// * `MPV_FORMAT_STRING` : `&'a str`
+/// * `MPV_FORMAT_STRING` : `String`, or `&'a str` when sending data only
 /// * `MPV_FORMAT_BOOL` : `bool`
-/// * `MPV_FORMAT_NODE` : unimplemented for now
-/// * `MPV_FORMAT_NODE_ARRAY` : unimplemented
-/// * `MPV_FORMAT_BYTE_ARRAY` : unimplemented, expected &'a [u8]
+/// * `MPV_FORMAT_NODE` : [`Node`](enum.Node.html)
+/// * `MPV_FORMAT_NODE_ARRAY` / `MPV_FORMAT_NODE_MAP` : only within a `Node`,
+///   as `Node::Array` and `Node::Map`
+/// * `MPV_FORMAT_BYTE_ARRAY` : `Vec<u8>`, or `&'a [u8]` when sending data only.
+///   libmpv only uses byte arrays within nodes, so these are sent and received
+///   as a `MPV_FORMAT_NODE`
 ///
+/// Types which can also be received from mpv (`get_property`, ...) implement
+/// [`MpvFormatGet`](trait.MpvFormatGet.html) as well.
 
 pub trait MpvFormat {
-    fn call_as_c_void<F : FnMut(*mut c_void)>(&self,f:F);
-    fn get_from_c_void<F : FnMut(*mut c_void)>(f: F) -> Self;
+    /// Calls `f` with a pointer to this value converted into the libmpv representation.
+    ///
+    /// Fails if the value cannot be represented for libmpv, for instance a string with
+    /// an interior nul byte.
+    fn call_as_c_void<F : FnMut(*mut c_void)>(&self,f:F) -> Result<()>;
     fn get_mpv_format() -> MpvInternalFormat ;
 }
 
+/// This trait is implemented by the `MpvFormat` types which can be received
+/// from mpv, such as with get_property.
+///
+/// Borrowed types such as `&'a str` or `&'a [u8]` can only be sent, use their owned
+/// counterpart to receive data.
+pub trait MpvFormatGet : MpvFormat {
+    /// Calls `f` with a pointer libmpv can write to, and converts what has been written.
+    ///
+    /// Fails if the value written by libmpv cannot be represented, for instance a string
+    /// which is not valid UTF-8.
+    fn get_from_c_void<F : FnMut(*mut c_void)>(f: F) -> Result<Self> where Self: Sized;
+
+    /// Converts a value received in an event, such as `Event::GetPropertyReply`.
+    ///
+    /// Fails with `Error::FormatMismatch` if the value has another format.
+    fn from_format(format: Format) -> Result<Self> where Self: Sized;
+}
+
+fn format_mismatch(expected: &'static str, found: &Format) -> Error {
+    Error::FormatMismatch {
+        expected,
+        found: found.format_name(),
+    }
+}
+
 impl MpvFormat for f64 {
-    fn call_as_c_void<F : FnMut(*mut c_void)>(&self,mut f:F){
+    fn call_as_c_void<F : FnMut(*mut c_void)>(&self,mut f:F) -> Result<()> {
         let mut cpy = *self;
         let pointer = &mut cpy as *mut _ as *mut c_void;
-        f(pointer)
+        f(pointer);
+        Ok(())
     }
 
-    fn get_from_c_void<F : FnMut(*mut c_void)>(mut f: F) -> f64 {
+    fn get_mpv_format() -> MpvInternalFormat {
+        MpvInternalFormat::MPV_FORMAT_DOUBLE
+    }
+}
+
+impl MpvFormatGet for f64 {
+    fn get_from_c_void<F : FnMut(*mut c_void)>(mut f: F) -> Result<f64> {
         let mut ret_value = 0.0;
         let pointer = &mut ret_value as *mut _ as *mut c_void;
         f(pointer);
-        ret_value
+        Ok(ret_value)
     }
 
-    fn get_mpv_format() -> MpvInternalFormat {
-        MpvInternalFormat::MPV_FORMAT_DOUBLE
+    fn from_format(format: Format) -> Result<f64> {
+        match format {
+            Format::Double(d) => Ok(d),
+            format => Err(format_mismatch("double", &format)),
+        }
     }
 }
 
 impl MpvFormat for i64 {
-    fn call_as_c_void<F : FnMut(*mut c_void)>(&self,mut f:F){
+    fn call_as_c_void<F : FnMut(*mut c_void)>(&self,mut f:F) -> Result<()> {
         let mut cpy = *self;
         let pointer = &mut cpy as *mut _ as *mut c_void;
-        f(pointer)
+        f(pointer);
+        Ok(())
     }
 
-    fn get_from_c_void<F : FnMut(*mut c_void)>(mut f:F) -> i64 {
+    fn get_mpv_format() -> MpvInternalFormat {
+        MpvInternalFormat::MPV_FORMAT_INT64
+    }
+}
+
+impl MpvFormatGet for i64 {
+    fn get_from_c_void<F : FnMut(*mut c_void)>(mut f:F) -> Result<i64> {
         let mut ret_value = 0;
         let pointer = &mut ret_value as *mut _ as *mut c_void;
         f(pointer);
-        ret_value
+        Ok(ret_value)
     }
 
-    fn get_mpv_format() -> MpvInternalFormat {
-        MpvInternalFormat::MPV_FORMAT_INT64
+    fn from_format(format: Format) -> Result<i64> {
+        match format {
+            Format::Int(i) => Ok(i),
+            format => Err(format_mismatch("int64", &format)),
+        }
     }
 }
 
 impl MpvFormat for bool {
-    fn call_as_c_void<F : FnMut(*mut c_void)>(&self,mut f:F){
+    fn call_as_c_void<F : FnMut(*mut c_void)>(&self,mut f:F) -> Result<()> {
         let mut cpy = if *self {
             1
         } else {
             0
         } ;
         let pointer = &mut cpy as *mut _ as *mut c_void;
-        f(pointer)
+        f(pointer);
+        Ok(())
+    }
+
+    fn get_mpv_format() -> MpvInternalFormat {
+        MpvInternalFormat::MPV_FORMAT_FLAG
     }
+}
 
-    fn get_from_c_void<F : FnMut(*mut c_void)>(mut f:F) -> bool {
+impl MpvFormatGet for bool {
+    fn get_from_c_void<F : FnMut(*mut c_void)>(mut f:F) -> Result<bool> {
         let mut temp_int = c_int::default() ;
         let pointer = &mut temp_int as *mut _ as *mut c_void;
         f(pointer);
-        match temp_int {
-            0 => false,
-            1 => true,
-            _ => unreachable!()
+        Ok(temp_int != 0)
+    }
+
+    fn from_format(format: Format) -> Result<bool> {
+        match format {
+            Format::Flag(b) => Ok(b),
+            format => Err(format_mismatch("flag", &format)),
         }
     }
+}
+
+fn call_str_as_c_void<F : FnMut(*mut c_void)>(string:&str,mut f:F) -> Result<()> {
+    let string = ffi::CString::new(string)?;
+    let mut ptr = string.as_ptr();
+    // mpv expects a *mut c_void pointing to a char*, but doesn't modify the string
+    f(&mut ptr as *mut *const c_char as *mut c_void);
+    Ok(())
+}
+
+/// Retrieves a string allocated by libmpv, copies it and frees the original.
+fn get_string_from_c_void<F : FnMut(*mut c_void)>(mut f:F) -> Result<String> {
+    let mut char_ptr = ptr::null_mut() as *mut c_char;
+    f(&mut char_ptr as *mut *mut c_char as *mut c_void);
+    if char_ptr.is_null() {
+        // if this is still a nullptr (like, in an error)
+        // there is nothing to copy nor to free
+        return Ok(String::new());
+    }
+    let string = unsafe {
+        CStr::from_ptr(char_ptr)
+             .to_str()
+             .map(|s| s.to_owned())
+    };
+    unsafe { mpv_free(char_ptr as *mut c_void) };
+    Ok(string?)
+}
+
+impl MpvFormat for &str {
+    fn call_as_c_void<F : FnMut(*mut c_void)>(&self,f:F) -> Result<()> {
+        call_str_as_c_void(self,f)
+    }
 
     fn get_mpv_format() -> MpvInternalFormat {
-        MpvInternalFormat::MPV_FORMAT_FLAG
+        MpvInternalFormat::MPV_FORMAT_STRING
     }
 }
 
-impl<'a> MpvFormat for &'a str {
-    fn call_as_c_void<F : FnMut(*mut c_void)>(&self,mut f:F){
-        let string = ffi::CString::new(*self).unwrap();
-        let ptr = string.as_ptr();
-        // transmute needed for *const -> *mut
-        // Should be ok since mpv doesn't modify *ptr
-        f(unsafe {mem::transmute(&ptr)})
-    }
-
-    fn get_from_c_void<F : FnMut(*mut c_void)>(mut f:F) -> &'a str {
-        let mut char_ptr = ptr::null_mut() as *mut c_void;
-        f(&mut char_ptr as *mut *mut c_void as *mut c_void);
-        if char_ptr.is_null() {
-            // if this is still a nullptr (like, in an error)
-            // the code below will segfault
-            // since it runs *before* checking for an mpv error
-            return "";
-        }
-        let return_str = unsafe {
-            CStr::from_ptr(char_ptr as *mut c_char)
-                 .to_str()
-                 .unwrap()
-        };
-        unsafe {mpv_free(char_ptr)};
-        return_str
+impl MpvFormat for String {
+    fn call_as_c_void<F : FnMut(*mut c_void)>(&self,f:F) -> Result<()> {
+        call_str_as_c_void(self,f)
     }
 
     fn get_mpv_format() -> MpvInternalFormat {
@@ -322,34 +458,131 @@ impl<'a> MpvFormat for &'a str {
     }
 }
 
-impl<'a> MpvFormat for OsdString<'a> {
-    fn call_as_c_void<F : FnMut(*mut c_void)>(&self,mut f:F){
-        let string = ffi::CString::new(self.string).unwrap();
-        let ptr = string.as_ptr();
-        // transmute needed for *const -> *mut
-        // Should be ok since mpv doesn't modify *ptr
-        f(unsafe {mem::transmute(&ptr)})
-    }
-
-    fn get_from_c_void<F : FnMut(*mut c_void)>(mut f:F) -> OsdString<'a> {
-        let mut char_ptr = ptr::null_mut() as *mut c_void;
-        f(&mut char_ptr as *mut *mut c_void as *mut c_void);
-        if char_ptr.is_null() {
-            // if this is still a nullptr (like, in an error)
-            // the code below will segfault
-            // since it runs *before* checking for an mpv error
-            return OsdString{string:""};
+impl MpvFormatGet for String {
+    fn get_from_c_void<F : FnMut(*mut c_void)>(f:F) -> Result<String> {
+        get_string_from_c_void(f)
+    }
+
+    fn from_format(format: Format) -> Result<String> {
+        match format {
+            Format::Str(string) => Ok(string),
+            format => Err(format_mismatch("string", &format)),
         }
-        let return_str = unsafe {
-            CStr::from_ptr(char_ptr as *mut c_char)
-                 .to_str()
-                 .unwrap()
-        };
-        unsafe {mpv_free(mem::transmute(char_ptr))};
-        OsdString{string:return_str}
+    }
+}
+
+impl MpvFormat for OsdString {
+    fn call_as_c_void<F : FnMut(*mut c_void)>(&self,f:F) -> Result<()> {
+        call_str_as_c_void(&self.string,f)
     }
 
     fn get_mpv_format() -> MpvInternalFormat {
         MpvInternalFormat::MPV_FORMAT_OSD_STRING
     }
 }
+
+impl MpvFormatGet for OsdString {
+    fn get_from_c_void<F : FnMut(*mut c_void)>(f:F) -> Result<OsdString> {
+        Ok(OsdString{string:get_string_from_c_void(f)?})
+    }
+
+    fn from_format(format: Format) -> Result<OsdString> {
+        match format {
+            Format::OsdStr(string) => Ok(OsdString { string }),
+            format => Err(format_mismatch("OSD string", &format)),
+        }
+    }
+}
+
+impl MpvFormat for Node {
+    fn call_as_c_void<F : FnMut(*mut c_void)>(&self,mut f:F) -> Result<()> {
+        let mut storage = MpvNodeStorage::default();
+        let mut node = storage.build(self)?;
+        f(&mut node as *mut mpv_node as *mut c_void);
+        Ok(())
+    }
+
+    fn get_mpv_format() -> MpvInternalFormat {
+        MpvInternalFormat::MPV_FORMAT_NODE
+    }
+}
+
+impl MpvFormatGet for Node {
+    fn get_from_c_void<F : FnMut(*mut c_void)>(mut f:F) -> Result<Node> {
+        // a zeroed node has the format MPV_FORMAT_NONE, which is what we
+        // get back if mpv returns an error
+        let mut node = mpv_node::default();
+        f(&mut node as *mut mpv_node as *mut c_void);
+        let ret = unsafe { Node::from_mpv_node(&node) };
+        unsafe { mpv_free_node_contents(&mut node) };
+        Ok(ret)
+    }
+
+    fn from_format(format: Format) -> Result<Node> {
+        match format {
+            Format::Node(node) => Ok(node),
+            Format::None => Ok(Node::None),
+            format => Err(format_mismatch("node", &format)),
+        }
+    }
+}
+
+fn call_bytes_as_c_void<F : FnMut(*mut c_void)>(bytes:&[u8],mut f:F) -> Result<()> {
+    let mut byte_array = mpv_byte_array {
+        data: bytes.as_ptr() as *mut c_void,
+        size: bytes.len() as _,
+    };
+    let mut node = mpv_node {
+        u: Default::default(),
+        format: MpvInternalFormat::MPV_FORMAT_BYTE_ARRAY,
+    };
+    unsafe { *node.u.ba() = &mut byte_array as *mut mpv_byte_array };
+    f(&mut node as *mut mpv_node as *mut c_void);
+    Ok(())
+}
+
+impl MpvFormat for Vec<u8> {
+    fn call_as_c_void<F : FnMut(*mut c_void)>(&self,f:F) -> Result<()> {
+        call_bytes_as_c_void(self,f)
+    }
+
+    fn get_mpv_format() -> MpvInternalFormat {
+        MpvInternalFormat::MPV_FORMAT_NODE
+    }
+}
+
+impl MpvFormatGet for Vec<u8> {
+    fn get_from_c_void<F : FnMut(*mut c_void)>(f:F) -> Result<Vec<u8>> {
+        match Node::get_from_c_void(f)? {
+            Node::ByteArray(bytes) => Ok(bytes),
+            // returned when mpv_get_property failed
+            Node::None => Ok(Vec::new()),
+            node => Err(Error::FormatMismatch {
+                expected: "byte array",
+                found: node.format_name()
+            })
+        }
+    }
+
+    fn from_format(format: Format) -> Result<Vec<u8>> {
+        match format {
+            Format::ByteArray(bytes) |
+            Format::Node(Node::ByteArray(bytes)) => Ok(bytes),
+            Format::Node(node) => Err(Error::FormatMismatch {
+                expected: "byte array",
+                found: node.format_name()
+            }),
+            format => Err(format_mismatch("byte array", &format)),
+        }
+    }
+}
+
+impl MpvFormat for &[u8] {
+    fn call_as_c_void<F : FnMut(*mut c_void)>(&self,f:F) -> Result<()> {
+        call_bytes_as_c_void(self,f)
+    }
+
+    fn get_mpv_format() -> MpvInternalFormat {
+        MpvInternalFormat::MPV_FORMAT_NODE
+    }
+}
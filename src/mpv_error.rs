@@ -1,23 +1,163 @@
-use std::{result, ffi, fmt, error};
-use std::error::Error as StdError;
+use std::{result, ffi, fmt, error, str};
 pub use num::FromPrimitive;
 
 use mpv_gen::mpv_error_string;
-pub use mpv_gen::Error;
+pub use mpv_gen::Error as MpvErrorCode;
+
+/// The error type of the mpv-rs crate
+///
+/// Errors returned by libmpv are stored in `Error::Mpv`. Errors which happen on the Rust side
+/// have their own variant, so that invalid data received from or sent to mpv never results in
+/// a panic.
+///
+/// When an error happens while accessing a property, an option or a command, it is wrapped
+/// in `Error::Property`, `Error::Option` or `Error::Command` with the name of what failed.
+/// Use `mpv_code()` to retrieve the libmpv error code regardless of the context.
+#[derive(Debug, Clone)]
+pub enum Error {
+    /// An error code returned by libmpv
+    Mpv(MpvErrorCode),
+    /// libmpv returned an error code unknown to this crate
+    UnknownErrorCode(i32),
+    /// libmpv returned an end file reason unknown to this crate
+    UnknownEndFileReason(i32),
+    /// A string sent to libmpv (a property name, an option, a command argument, ...)
+    /// contained an interior nul byte
+    InteriorNul(ffi::NulError),
+    /// A string received from libmpv was not valid UTF-8
+    InvalidUtf8(str::Utf8Error),
+    /// The value received from libmpv does not have the expected format
+    FormatMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// A map received from libmpv lacks a required field, or the field has another format
+    MissingField(&'static str),
+    /// The error happened while accessing the property `name`
+    Property {
+        name: String,
+        source: Box<Error>,
+    },
+    /// The error happened while setting the option `name`
+    Option {
+        name: String,
+        source: Box<Error>,
+    },
+    /// The error happened while running the command `name`
+    Command {
+        name: String,
+        source: Box<Error>,
+    },
+}
 
 pub type Result<T> = result::Result<T, Error>;
 
-impl error::Error for Error {
-    fn description(&self) -> &str {
+impl Error {
+    /// Returns the libmpv error code behind this error, if any
+    pub fn mpv_code(&self) -> Option<MpvErrorCode> {
+        match *self {
+            Error::Mpv(code) => Some(code),
+            Error::Property { ref source, .. } |
+            Error::Option { ref source, .. } |
+            Error::Command { ref source, .. } => source.mpv_code(),
+            _ => None,
+        }
+    }
+}
+
+impl MpvErrorCode {
+    /// Returns the libmpv description of this error code
+    pub fn as_str(&self) -> &str {
         let str_ptr = unsafe { mpv_error_string(*self as ::std::os::raw::c_int) };
         assert!(!str_ptr.is_null());
         unsafe { ffi::CStr::from_ptr(str_ptr).to_str().unwrap() }
     }
 }
 
+impl error::Error for MpvErrorCode {}
+
+impl fmt::Display for MpvErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({:?})", self.as_str(), self)
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::InteriorNul(ref e) => Some(e),
+            Error::InvalidUtf8(ref e) => Some(e),
+            Error::Property { ref source, .. } |
+            Error::Option { ref source, .. } |
+            Error::Command { ref source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} ({:?})", self.description(), self)
+        match *self {
+            Error::Mpv(ref code) => code.fmt(f),
+            Error::UnknownErrorCode(code) => write!(f, "unknown mpv error code {}", code),
+            Error::UnknownEndFileReason(reason) => write!(f, "unknown end file reason {}", reason),
+            Error::InteriorNul(_) => write!(f, "string sent to mpv contains a nul byte"),
+            Error::InvalidUtf8(_) => write!(f, "string received from mpv is not valid UTF-8"),
+            Error::FormatMismatch { expected, found } => {
+                write!(f, "expected a {} from mpv, got a {}", expected, found)
+            },
+            Error::MissingField(field) => write!(f, "missing field '{}' in a map from mpv", field),
+            Error::Property { ref name, .. } => write!(f, "error accessing property '{}'", name),
+            Error::Option { ref name, .. } => write!(f, "error setting option '{}'", name),
+            Error::Command { ref name, .. } => write!(f, "error running command '{}'", name),
+        }
+    }
+}
+
+impl From<MpvErrorCode> for Error {
+    fn from(code: MpvErrorCode) -> Error {
+        Error::Mpv(code)
+    }
+}
+
+impl From<ffi::NulError> for Error {
+    fn from(e: ffi::NulError) -> Error {
+        Error::InteriorNul(e)
+    }
+}
+
+impl From<str::Utf8Error> for Error {
+    fn from(e: str::Utf8Error) -> Error {
+        Error::InvalidUtf8(e)
+    }
+}
+
+/// Adds the name of the property, option or command to the error of a `Result`
+pub trait ResultExt {
+    fn property_context(self, name: &str) -> Self;
+    fn option_context(self, name: &str) -> Self;
+    fn command_context(self, name: &str) -> Self;
+}
+
+impl<T> ResultExt for Result<T> {
+    fn property_context(self, name: &str) -> Self {
+        self.map_err(|e| Error::Property { name: name.to_owned(), source: Box::new(e) })
+    }
+
+    fn option_context(self, name: &str) -> Self {
+        self.map_err(|e| Error::Option { name: name.to_owned(), source: Box::new(e) })
+    }
+
+    fn command_context(self, name: &str) -> Self {
+        self.map_err(|e| Error::Command { name: name.to_owned(), source: Box::new(e) })
+    }
+}
+
+/// Converts an int error code (sent by libmpv) into an `Error`
+pub fn error_from_code(code: i32) -> Error {
+    match MpvErrorCode::from_i32(code) {
+        Some(code) => Error::Mpv(code),
+        None => Error::UnknownErrorCode(code),
     }
 }
 
@@ -25,8 +165,13 @@ impl fmt::Display for Error {
 /// depending if the received int is 0 or something else
 pub fn ret_to_result<T>(ret: i32, default: T) -> Result<T> {
     if ret < 0 {
-        Err(Error::from_i32(ret).unwrap())
+        Err(error_from_code(ret))
     } else {
         Ok(default)
     }
 }
+
+/// utility function to convert a string sent to libmpv into a `CString`
+pub fn to_cstring(string: &str) -> Result<ffi::CString> {
+    Ok(ffi::CString::new(string)?)
+}
@@ -2,6 +2,7 @@
 #![allow(non_camel_case_types)]
 // automatically generated by rust-bindgen
 // rust-bindgen -l mpv -o src/mpv_gen.rs /usr/include/mpv/opengl_cb.h
+// the render.h and render_gl.h bindings have been added afterwards
 
 pub type ptrdiff_t = ::std::os::raw::c_long;
 pub type size_t = ::std::os::raw::c_ulong;
@@ -194,7 +195,8 @@ impl ::std::default::Default for Struct_mpv_node {
 #[repr(C)]
 #[derive(Copy)]
 pub struct Union_Unnamed2 {
-    pub _bindgen_data_: [u64; 1usize],
+    // MaybeUninit so that copying a node keeps the pointers it holds valid
+    pub _bindgen_data_: ::std::mem::MaybeUninit<[u64; 1usize]>,
 }
 impl Union_Unnamed2 {
     pub unsafe fn string(&mut self) -> *mut *mut ::std::os::raw::c_char {
@@ -417,6 +419,22 @@ impl ::std::default::Default for Struct_mpv_event_client_message {
 pub type mpv_event_client_message = Struct_mpv_event_client_message;
 #[repr(C)]
 #[derive(Copy)]
+pub struct Struct_mpv_event_command {
+    pub result: mpv_node,
+}
+impl ::std::clone::Clone for Struct_mpv_event_command {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl ::std::default::Default for Struct_mpv_event_command {
+    fn default() -> Self {
+        unsafe { ::std::mem::zeroed() }
+    }
+}
+pub type mpv_event_command = Struct_mpv_event_command;
+#[repr(C)]
+#[derive(Copy)]
 pub struct Struct_mpv_event {
     pub event_id: mpv_event_id,
     pub error: ::std::os::raw::c_int,
@@ -453,7 +471,86 @@ pub type mpv_opengl_cb_get_proc_address_fn =
     ::std::option::Option<unsafe extern "C" fn(fn_ctx: *mut ::std::os::raw::c_void,
                                                  name: *const ::std::os::raw::c_char)
                                                  -> *mut ::std::os::raw::c_void>;
-#[link(name = "mpv")]
+// render.h and render_gl.h
+pub enum Struct_mpv_render_context { }
+pub type mpv_render_context = Struct_mpv_render_context;
+#[derive(Clone, Copy, Debug)]
+#[repr(u32)]
+pub enum MpvRenderParamType {
+    MPV_RENDER_PARAM_INVALID = 0,
+    MPV_RENDER_PARAM_API_TYPE = 1,
+    MPV_RENDER_PARAM_OPENGL_INIT_PARAMS = 2,
+    MPV_RENDER_PARAM_OPENGL_FBO = 3,
+    MPV_RENDER_PARAM_FLIP_Y = 4,
+    MPV_RENDER_PARAM_DEPTH = 5,
+    MPV_RENDER_PARAM_ICC_PROFILE = 6,
+    MPV_RENDER_PARAM_AMBIENT_LIGHT = 7,
+    MPV_RENDER_PARAM_X11_DISPLAY = 8,
+    MPV_RENDER_PARAM_WL_DISPLAY = 9,
+    MPV_RENDER_PARAM_ADVANCED_CONTROL = 10,
+    MPV_RENDER_PARAM_NEXT_FRAME_INFO = 11,
+    MPV_RENDER_PARAM_BLOCK_FOR_TARGET_TIME = 12,
+    MPV_RENDER_PARAM_SKIP_RENDERING = 13,
+    MPV_RENDER_PARAM_DRM_DISPLAY = 14,
+    MPV_RENDER_PARAM_DRM_DRAW_SURFACE_SIZE = 15,
+    MPV_RENDER_PARAM_DRM_DISPLAY_V2 = 16,
+    MPV_RENDER_PARAM_SW_SIZE = 17,
+    MPV_RENDER_PARAM_SW_FORMAT = 18,
+    MPV_RENDER_PARAM_SW_STRIDE = 19,
+    MPV_RENDER_PARAM_SW_POINTER = 20,
+}
+pub type mpv_render_param_type = MpvRenderParamType;
+#[repr(C)]
+#[derive(Copy)]
+pub struct Struct_mpv_render_param {
+    pub _type: mpv_render_param_type,
+    pub data: *mut ::std::os::raw::c_void,
+}
+impl ::std::clone::Clone for Struct_mpv_render_param {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+pub type mpv_render_param = Struct_mpv_render_param;
+pub const MPV_RENDER_API_TYPE_OPENGL: &[u8; 7usize] = b"opengl\0";
+pub const MPV_RENDER_API_TYPE_SW: &[u8; 3usize] = b"sw\0";
+pub const MPV_RENDER_UPDATE_FRAME: uint64_t = 1;
+pub type mpv_render_update_fn =
+    ::std::option::Option<unsafe extern "C" fn(cb_ctx: *mut ::std::os::raw::c_void)>;
+#[repr(C)]
+#[derive(Copy)]
+pub struct Struct_mpv_opengl_init_params {
+    pub get_proc_address: mpv_opengl_cb_get_proc_address_fn,
+    pub get_proc_address_ctx: *mut ::std::os::raw::c_void,
+    pub extra_exts: *const ::std::os::raw::c_char,
+}
+impl ::std::clone::Clone for Struct_mpv_opengl_init_params {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+pub type mpv_opengl_init_params = Struct_mpv_opengl_init_params;
+#[repr(C)]
+#[derive(Copy)]
+pub struct Struct_mpv_opengl_fbo {
+    pub fbo: ::std::os::raw::c_int,
+    pub w: ::std::os::raw::c_int,
+    pub h: ::std::os::raw::c_int,
+    pub internal_format: ::std::os::raw::c_int,
+}
+impl ::std::clone::Clone for Struct_mpv_opengl_fbo {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl ::std::default::Default for Struct_mpv_opengl_fbo {
+    fn default() -> Self {
+        unsafe { ::std::mem::zeroed() }
+    }
+}
+pub type mpv_opengl_fbo = Struct_mpv_opengl_fbo;
+// the tests link against the stub of mpv_tests instead
+#[cfg_attr(not(test), link(name = "mpv"))]
 extern "C" {
     pub fn mpv_client_api_version() -> ::std::os::raw::c_ulong;
     pub fn mpv_error_string(error: ::std::os::raw::c_int) -> *const ::std::os::raw::c_char;
@@ -582,4 +679,23 @@ extern "C" {
                                      time: int64_t)
                                      -> ::std::os::raw::c_int;
     pub fn mpv_opengl_cb_uninit_gl(ctx: *mut mpv_opengl_cb_context) -> ::std::os::raw::c_int;
+    pub fn mpv_render_context_create(res: *mut *mut mpv_render_context,
+                                     mpv: *mut mpv_handle,
+                                     params: *mut mpv_render_param)
+                                     -> ::std::os::raw::c_int;
+    pub fn mpv_render_context_set_parameter(ctx: *mut mpv_render_context,
+                                            param: mpv_render_param)
+                                            -> ::std::os::raw::c_int;
+    pub fn mpv_render_context_get_info(ctx: *mut mpv_render_context,
+                                       param: mpv_render_param)
+                                       -> ::std::os::raw::c_int;
+    pub fn mpv_render_context_set_update_callback(ctx: *mut mpv_render_context,
+                                                  callback: mpv_render_update_fn,
+                                                  callback_ctx: *mut ::std::os::raw::c_void);
+    pub fn mpv_render_context_update(ctx: *mut mpv_render_context) -> uint64_t;
+    pub fn mpv_render_context_render(ctx: *mut mpv_render_context,
+                                     params: *mut mpv_render_param)
+                                     -> ::std::os::raw::c_int;
+    pub fn mpv_render_context_report_swap(ctx: *mut mpv_render_context);
+    pub fn mpv_render_context_free(ctx: *mut mpv_render_context);
 }
@@ -1,39 +1,90 @@
 use mpv_gen::{mpv_command, mpv_command_async, mpv_wait_event, mpv_create, mpv_initialize,
-              mpv_terminate_destroy, mpv_handle, mpv_set_option,
+              mpv_terminate_destroy, mpv_detach_destroy, mpv_handle, mpv_set_option,
               mpv_set_property, mpv_set_property_async, mpv_get_property,
-              mpv_get_property_async, mpv_opengl_cb_get_proc_address_fn, mpv_get_sub_api,
-              mpv_opengl_cb_uninit_gl, mpv_opengl_cb_init_gl, mpv_opengl_cb_draw,
-              mpv_opengl_cb_context, mpv_observe_property, mpv_unobserve_property,
-              mpv_opengl_cb_set_update_callback, mpv_get_time_us};
+              mpv_get_property_async, mpv_opengl_cb_get_proc_address_fn,
+              mpv_observe_property, mpv_unobserve_property, mpv_get_time_us, mpv_command_node,
+              mpv_command_node_async, mpv_node, mpv_free_node_contents, mpv_set_wakeup_callback,
+              mpv_wakeup, mpv_request_log_messages, mpv_request_event};
+use mpv_node::{Node, MpvNodeStorage};
+use mpv_metadata::metadata_from_node;
+use mpv_log::forward_log_message;
+use mpv_render::RenderContext;
+use mpv_async::{ReplyDispatcher, AsyncReply, INTERNAL_USERDATA_START, get_property_reply,
+                set_property_reply, command_reply};
 use mpv_enums::*;
 use mpv_error::*;
 
-use std::os::raw::c_void;
-use std::{ffi, ptr};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::boxed::Box;
-use std::ops::{Deref,DerefMut};
+use std::os::raw::{c_int, c_void};
+use std::{fmt, ptr};
+use std::time::{Duration, Instant};
+use std::sync::{Arc, Mutex};
 /// The main struct of the mpv-rs crate
 ///
 /// Almost every function from the libmpv API needs a context, which is stored in this struct.
 ///
-#[derive(Debug)]
+/// # Ownership
+///
+/// What happens to the player when a `MpvHandler` is dropped depends on its `Ownership` :
+///
+/// * the `MpvHandler` created by the builder is `Ownership::Primary` : dropping it stops
+///   the playback and destroys the player, which closes its window. The other clients
+///   receive `Event::Shutdown`, and the drop blocks until they are all dropped as well.
+/// * the clients created with `create_client` are `Ownership::Client` : dropping them only
+///   disconnects them, and the playback goes on. The player is destroyed when its last
+///   client is dropped, whatever their ownership.
+///
+/// The ownership can be changed with `set_ownership`, for instance to keep the player
+/// running after the handle created by the builder is dropped, which `detach()` also does.
+///
+/// # Threads
+///
+/// A `MpvHandler` can be moved to another thread, but is not `Sync` : functions such as
+/// `wait_event` must not be called concurrently on the same handle. Use a client to receive
+/// events on several threads.
 pub struct MpvHandler {
     handle: *mut mpv_handle,
+    // boxed twice so that a thin pointer can be given to libmpv
+    wakeup_callback: Option<Box<WakeupCallback>>,
+    replies: Arc<Mutex<ReplyDispatcher>>,
+    // the userdata of the `metadata` property, when observed with observe_metadata
+    metadata_observer: Option<u64>,
+    // whether log messages are sent to the `log` crate instead of being returned
+    forward_logs: bool,
+    ownership: Ownership,
 }
 
+// the functions of the client API of libmpv can be called from any thread, except for
+// mpv_wait_event which must not be called concurrently, which &mut self ensures. The wakeup
+// callback and the observers are Send, and the dispatcher is behind a mutex.
+unsafe impl Send for MpvHandler {}
+
+/// What happens to the player when a `MpvHandler` is dropped, see the documentation of
+/// `MpvHandler`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ownership {
+    /// The player is destroyed when the handle is dropped, with `mpv_terminate_destroy`
+    Primary,
+    /// Only the handle is destroyed when dropped, with `mpv_detach_destroy`
+    Client,
+}
 
-///
-/// This struct is a decorator of `MpvHandler`, and can use all the functions from `MpvHandler`.
-/// It is only used when you must embed mpv somewhere else using openGL.
-///
-#[derive(Debug)]
-pub struct MpvHandlerWithGl {
-    mpv_handler:     MpvHandler,
-    gl_context:      *mut mpv_opengl_cb_context,
-    update_available:AtomicBool
+/// A callback called by libmpv when new events are available, see `set_wakeup_callback`
+pub type WakeupCallback = Box<dyn Fn() + Send + Sync>;
+
+impl fmt::Debug for MpvHandler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MpvHandler")
+         .field("handle", &self.handle)
+         .field("wakeup_callback", &self.wakeup_callback.is_some())
+         .field("replies", &self.replies)
+         .field("metadata_observer", &self.metadata_observer)
+         .field("forward_logs", &self.forward_logs)
+         .field("ownership", &self.ownership)
+         .finish()
+    }
 }
 
+
 #[derive(Debug)]
 pub struct MpvHandlerBuilder {
     handle: *mut mpv_handle,
@@ -43,7 +94,8 @@ pub struct MpvHandlerBuilder {
 ///
 /// * **Step 1** : call `MpvHandler::new()` to create a Builder.
 /// * **Step 2** : Add options to your player
-/// * **Step 3** : Finish creating your `MpvHandler`, either with `build()` or `build_with_gl(...)`
+/// * **Step 3** : Finish creating your `MpvHandler`, either with `build()`, `build_with_gl(...)`
+///   or `build_with_sw_render()`
 ///
 impl MpvHandlerBuilder {
 
@@ -56,8 +108,8 @@ impl MpvHandlerBuilder {
     #[must_use]
     pub fn new() -> Result<Self> {
         let handle = unsafe { mpv_create() };
-        if handle == ptr::null_mut() {
-            return Err(Error::MPV_ERROR_NOMEM);
+        if handle.is_null() {
+            return Err(Error::Mpv(MpvErrorCode::MPV_ERROR_NOMEM));
         }
         ret_to_result(0,MpvHandlerBuilder { handle:     handle })
     }
@@ -72,19 +124,19 @@ impl MpvHandlerBuilder {
     /// // set other options
     /// // Build the MpvHandler later
     /// ```
-    #[cfg_attr(feature = "clippy", allow(temporary_cstring_as_ptr))]
     pub fn set_option<T : MpvFormat>(&mut self, property: &str, option: T) -> Result<()> {
+        let c_property = to_cstring(property).option_context(property)?;
         let mut ret = 0 ;
         let format = T::get_mpv_format();
         option.call_as_c_void(|ptr:*mut c_void|{
             ret = unsafe {
                 mpv_set_option(self.handle,
-                               ffi::CString::new(property).unwrap().as_ptr(),
+                               c_property.as_ptr(),
                                format,
                                ptr)
             }
-        });
-        ret_to_result(ret,())
+        }).option_context(property)?;
+        ret_to_result(ret,()).option_context(property)
     }
 
     /// shortcut for `set_option("hwdec","auto")`
@@ -94,18 +146,48 @@ impl MpvHandlerBuilder {
         self.set_option("hwdec","auto")
     }
 
+    ///
+    /// Stop receiving the given kinds of events, such as the frequent `MPV_EVENT_TICK`,
+    /// from the start. They can be enabled again with `MpvHandler::enable_event`.
+    ///
+    /// # Example
+    /// ```
+    /// use mpv::MpvEventId;
+    ///
+    /// let mut mpv_builder = mpv::MpvHandlerBuilder::new().expect("Failed to init MPV builder");
+    /// mpv_builder.disable_events(&[MpvEventId::MPV_EVENT_TICK,
+    ///                              MpvEventId::MPV_EVENT_TRACK_SWITCHED])
+    ///            .expect("Failed to disable events");
+    /// ```
+    pub fn disable_events(&mut self, events: &[MpvEventId]) -> Result<()> {
+        for event in events {
+            request_event(self.handle, *event, false)?;
+        }
+        Ok(())
+    }
+
     ///
     /// Finish creating your player. It will spawn a new window on your window manager.
-    /// Note that it returns a Box of MpvHandler because it needs to be allocated on the heap;
-    /// The Rust MpvHandler gives its own pointer the the C mpv API, and moving the MpvHandler
-    /// within the stack is forbidden in that case.
+    ///
+    /// The returned `MpvHandler` can be moved freely. Its ownership is
+    /// [`Ownership::Primary`](enum.Ownership.html) : dropping it destroys the player, unless
+    /// it is changed with `set_ownership` or the handler is dropped with `detach()`.
     #[must_use]
     pub fn build(self) -> Result<MpvHandler> {
         let ret = unsafe { mpv_initialize(self.handle) };
 
-        ret_to_result(ret,MpvHandler {
-            handle:             self.handle,
-        })
+        ret_to_result(ret,MpvHandler::from_handle(self.handle, Ownership::Primary))
+    }
+
+    ///
+    /// Finish creating your player, using the software renderer of libmpv. It will **not** spawn
+    /// a new window on your window manager, but instead render the video into memory, with
+    /// `RenderContext::render_sw(...)`. No GPU is required.
+    ///
+    /// The "vo" option is set to "libmpv" by this function.
+    pub fn build_with_sw_render(mut self) -> Result<RenderContext> {
+        self.set_option("vo", "libmpv")?;
+        RenderContext::new_sw(self.build()?)
     }
 
     ///
@@ -126,161 +208,135 @@ impl MpvHandlerBuilder {
     #[must_use]
     pub fn build_with_gl(mut self,
                          get_proc_address: mpv_opengl_cb_get_proc_address_fn,
-                         get_proc_address_ctx: *mut ::std::os::raw::c_void) -> Result<Box<MpvHandlerWithGl>> {
-        self.set_option("vo", "opengl-cb").expect("Error setting vo option to opengl-cb");
-        let mpv_handler_result = self.build();
-        match mpv_handler_result {
-            Ok(mpv_handler) => {
-                let opengl_ctx = unsafe {
-                    mpv_get_sub_api(mpv_handler.handle,
-                                    SubApi::MPV_SUB_API_OPENGL_CB)
-                } as *mut mpv_opengl_cb_context ;
-                let ret = unsafe {
-                    mpv_opengl_cb_init_gl(opengl_ctx, ptr::null(), get_proc_address, get_proc_address_ctx)
-                };
-                // Actually using the opengl_cb state has to be explicitly requested.
-                // Otherwise, mpv will create a separate platform window.
-
-                let mut mpv_handler_with_gl = Box::new(MpvHandlerWithGl {
-                    mpv_handler:      mpv_handler,
-                    gl_context:       opengl_ctx,
-                    update_available: AtomicBool::new(false)
-                });
-
-                // Additional callback
-                unsafe {mpv_opengl_cb_set_update_callback(opengl_ctx,
-                                                          Some(MpvHandlerWithGl::update_draw),
-                                                          mpv_handler_with_gl.as_mut() as *mut MpvHandlerWithGl as *mut c_void)};
-                ret_to_result(ret,mpv_handler_with_gl)
-            },
-            Err(e) => {
-                Err(e)
-            }
-        }
-    }
-}
-
-impl Deref for MpvHandlerWithGl {
-    type Target = MpvHandler;
-    fn deref(&self) -> &MpvHandler {
-        &self.mpv_handler
+                         get_proc_address_ctx: *mut ::std::os::raw::c_void) -> Result<RenderContext> {
+        // Actually using the render API has to be explicitly requested.
+        // Otherwise, mpv will create a separate platform window.
+        self.set_option("vo", "libmpv")?;
+        RenderContext::new_gl(self.build()?, get_proc_address, get_proc_address_ctx)
     }
 }
 
-impl DerefMut for MpvHandlerWithGl {
-    fn deref_mut(&mut self) -> &mut MpvHandler {
-        &mut self.mpv_handler
-    }
-}
-
-impl MpvHandlerWithGl {
-    /// Render video
-    ///
-    /// The video will use the full provided framebuffer. Options like "panscan" are
-    /// applied to determine which part of the video should be visible and how the
-    /// video should be scaled. You can change these options at runtime by using the
-    /// mpv property API.
-    ///
-    /// fbo is the framebuffer object to render on. Because the renderer might
-    /// manage multiple FBOs internally for the purpose of video
-    /// postprocessing, it will always bind and unbind FBOs itself. If
-    /// you want mpv to render on the main framebuffer, pass 0.
-    ///
-    /// width is the width of the framebuffer. This is either the video size if the fbo
-    /// parameter is 0, or the allocated size of the texture backing the
-    /// fbo. The renderer will always use the full size of the fbo.
-    ///
-    /// height is the height of the framebuffer. Same as with the w parameter, except
-    /// that this parameter can be negative. In this case, the video
-    /// frame will be rendered flipped.
-    ///
-    /// # Errors
-    ///
-    /// If the external video module has not been configured correctly, libmpv can send various
-    /// errors such as MPV_ERROR_UNSUPPORTED
-    ///
-    pub fn draw(&mut self, fbo: i32, width: i32, heigth: i32) -> Result<()> {
-        self.update_available.store(false,Ordering::Relaxed) ;
-        let ret = unsafe { mpv_opengl_cb_draw(self.gl_context, fbo, width, heigth) };
-        ret_to_result(ret, ())
-    }
-
-    unsafe extern "C" fn update_draw(cb_ctx: *mut ::std::os::raw::c_void) {
-        let ptr = cb_ctx as *mut MpvHandlerWithGl ;
-        assert!(!ptr.is_null());
-        let mpv = &mut (*ptr) ;
-        mpv.update_available.store(true, Ordering::Relaxed);
+impl MpvHandler {
+    fn from_handle(handle: *mut mpv_handle, ownership: Ownership) -> MpvHandler {
+        MpvHandler {
+            handle,
+            wakeup_callback: None,
+            replies: ReplyDispatcher::new(),
+            metadata_observer: None,
+            forward_logs: false,
+            ownership,
+        }
     }
 
-    /// returns true if another frame is available
-    pub fn is_update_available(&self) -> bool {
-        self.update_available.load(Ordering::Relaxed)
+    /// Wraps the handle of a client created with `mpv_create_client`, which is detached
+    /// instead of terminated when dropped
+    pub(crate) fn from_client_handle(handle: *mut mpv_handle) -> MpvHandler {
+        MpvHandler::from_handle(handle, Ownership::Client)
     }
-}
-
-impl MpvHandler {
 
     /// Set a property synchronously
-    #[cfg_attr(feature = "clippy", allow(temporary_cstring_as_ptr))]
     pub fn set_property<T : MpvFormat>(&mut self, property: &str, value : T) -> Result<()>{
+        mpv_span!("set_property", property);
+        let c_property = to_cstring(property).property_context(property)?;
         let mut ret = 0 ;
         let format = T::get_mpv_format();
         value.call_as_c_void(|ptr:*mut c_void|{
             ret = unsafe {
                 mpv_set_property(self.handle,
-                                 ffi::CString::new(property).unwrap().as_ptr(),
+                                 c_property.as_ptr(),
                                  format,
                                  ptr)
             }
-        });
-        ret_to_result(ret,())
+        }).property_context(property)?;
+        ret_to_result(ret,()).property_context(property)
     }
 
     /// Set a property asynchronously
-    #[cfg_attr(feature = "clippy", allow(temporary_cstring_as_ptr))]
-    pub fn set_property_async<T : MpvFormat>(&mut self, property: &str, value : T, userdata:u32) -> Result<()>{
-        let userdata = userdata as ::std::os::raw::c_ulong;
+    ///
+    /// The returned future is resolved once the property has been set.
+    pub fn set_property_async<T : MpvFormat>(&mut self, property: &str, value : T) -> AsyncReply<()> {
+        let userdata = self.register_reply();
+        match self.set_property_async_raw(property, value, userdata) {
+            Ok(()) => AsyncReply::waiting(self.replies.clone(), userdata, property, set_property_reply),
+            Err(e) => self.reply_failed(userdata, e),
+        }
+    }
+
+    /// Set a property asynchronously, the reply being returned as an
+    /// `Event::SetPropertyReply` with the given userdata
+    pub fn set_property_async_with_userdata<T : MpvFormat>(&mut self, property: &str, value : T, userdata:u32) -> Result<()>{
+        self.set_property_async_raw(property, value, userdata as u64)
+    }
+
+    fn set_property_async_raw<T : MpvFormat>(&mut self, property: &str, value : T, userdata:u64) -> Result<()>{
+        mpv_event!(property, reply_userdata = userdata, "set_property_async");
+        let c_property = to_cstring(property).property_context(property)?;
         let mut ret = 0 ;
         let format = T::get_mpv_format();
         value.call_as_c_void(|ptr:*mut c_void|{
             ret = unsafe {
                 mpv_set_property_async(self.handle,
                                        userdata,
-                                       ffi::CString::new(property).unwrap().as_ptr(),
+                                       c_property.as_ptr(),
                                        format,
                                        ptr)
             }
-        });
-        ret_to_result(ret,())
+        }).property_context(property)?;
+        ret_to_result(ret,()).property_context(property)
     }
 
     /// Get a property synchronously
-    #[cfg_attr(feature = "clippy", allow(temporary_cstring_as_ptr))]
-    pub fn get_property<T : MpvFormat>(&self, property: &str) -> Result<T> {
+    pub fn get_property<T : MpvFormatGet>(&self, property: &str) -> Result<T> {
+        mpv_span!("get_property", property);
+        let c_property = to_cstring(property).property_context(property)?;
         let mut ret = 0 ;
         let format = T::get_mpv_format();
         let result = T::get_from_c_void(|ptr:*mut c_void|{
             ret = unsafe {
                 mpv_get_property(self.handle,
-                                 ffi::CString::new(property).unwrap().as_ptr(),
+                                 c_property.as_ptr(),
                                  format,
                                  ptr)
             }
         });
-        ret_to_result(ret,result)
+        ret_to_result(ret,()).and(result).property_context(property)
     }
 
     /// Get a property asynchronously
-    #[cfg_attr(feature = "clippy", allow(temporary_cstring_as_ptr))]
-    pub fn get_property_async<T : MpvFormat>(&self, property: &str, userdata :u32) -> Result<()> {
-        let userdata = userdata as ::std::os::raw::c_ulong;
+    ///
+    /// The returned future is resolved with the value of the property.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # let mpv = mpv::MpvHandlerBuilder::new().unwrap().build().unwrap();
+    /// let volume = mpv.get_property_async::<f64>("volume");
+    /// // keep calling mpv.wait_event(...) while awaiting the result,
+    /// // for instance in another task with mpv.event_stream()
+    /// ```
+    pub fn get_property_async<T : MpvFormatGet>(&self, property: &str) -> AsyncReply<T> {
+        let userdata = self.register_reply();
+        match self.get_property_async_raw::<T>(property, userdata) {
+            Ok(()) => AsyncReply::waiting(self.replies.clone(), userdata, property, get_property_reply::<T>),
+            Err(e) => self.reply_failed(userdata, e),
+        }
+    }
+
+    /// Get a property asynchronously, the reply being returned as an
+    /// `Event::GetPropertyReply` with the given userdata
+    pub fn get_property_async_with_userdata<T : MpvFormat>(&self, property: &str, userdata :u32) -> Result<()> {
+        self.get_property_async_raw::<T>(property, userdata as u64)
+    }
+
+    fn get_property_async_raw<T : MpvFormat>(&self, property: &str, userdata :u64) -> Result<()> {
+        mpv_event!(property, reply_userdata = userdata, "get_property_async");
+        let c_property = to_cstring(property).property_context(property)?;
         let ret = unsafe {
             mpv_get_property_async(self.handle,
                                    userdata,
-                                   ffi::CString::new(property).unwrap().as_ptr(),
+                                   c_property.as_ptr(),
                                    T::get_mpv_format())
         };
-        ret_to_result(ret,())
+        ret_to_result(ret,()).property_context(property)
     }
 
     ///
@@ -293,26 +349,29 @@ impl MpvHandler {
     ///
     /// It is preferred that you initialize your options with the Builder instead
     ///
-    #[cfg_attr(feature = "clippy", allow(temporary_cstring_as_ptr))]
     pub fn set_option<T : MpvFormat>(&mut self, property: &str, option: T) -> Result<()> {
+        let c_property = to_cstring(property).option_context(property)?;
         let mut ret = 0 ;
         let format = T::get_mpv_format();
         option.call_as_c_void(|ptr:*mut c_void|{
             ret = unsafe {
                 mpv_set_option(self.handle,
-                                 ffi::CString::new(property).unwrap().as_ptr(),
+                                 c_property.as_ptr(),
                                  format,
                                  ptr)
             }
-        });
-        ret_to_result(ret,())
+        }).option_context(property)?;
+        ret_to_result(ret,()).option_context(property)
     }
 
     /// Send a command synchronously
     pub fn command(&mut self, command: &[&str]) -> Result<()> {
-        let command_cstring: Vec<_> = command.iter()
-                                             .map(|item| ffi::CString::new(*item).unwrap())
-                                             .collect();
+        let name = command.first().cloned().unwrap_or("");
+        mpv_span!("command", command = name);
+        let command_cstring = command.iter()
+                                     .map(|item| to_cstring(item))
+                                     .collect::<Result<Vec<_>>>()
+                                     .command_context(name)?;
         let mut command_pointers: Vec<_> = command_cstring.iter()
                                                           .map(|item| item.as_ptr())
                                                           .collect();
@@ -320,22 +379,131 @@ impl MpvHandler {
 
         let ret = unsafe { mpv_command(self.handle, command_pointers.as_mut_ptr()) };
 
-        ret_to_result(ret, ())
+        ret_to_result(ret, ()).command_context(name)
     }
 
     /// Send a command asynchronously
-    pub fn command_async(&mut self, command: &[&str], userdata :u32) -> Result<()> {
-        let userdata = userdata as ::std::os::raw::c_ulong;
-        let command_cstring: Vec<_> = command.iter()
-                                             .map(|item| ffi::CString::new(*item).unwrap())
-                                             .collect();
+    ///
+    /// The returned future is resolved with the result of the command, which is
+    /// `Node::None` for most commands.
+    pub fn command_async(&mut self, command: &[&str]) -> AsyncReply<Node> {
+        let name = command.first().cloned().unwrap_or("");
+        let userdata = self.register_reply();
+        match self.command_async_raw(command, userdata) {
+            Ok(()) => AsyncReply::waiting(self.replies.clone(), userdata, name, command_reply),
+            Err(e) => self.reply_failed(userdata, e),
+        }
+    }
+
+    /// Send a command asynchronously, the reply being returned as an
+    /// `Event::CommandReply` with the given userdata
+    pub fn command_async_with_userdata(&mut self, command: &[&str], userdata :u32) -> Result<()> {
+        self.command_async_raw(command, userdata as u64)
+    }
+
+    fn command_async_raw(&mut self, command: &[&str], userdata :u64) -> Result<()> {
+        let name = command.first().cloned().unwrap_or("");
+        mpv_event!(command = name, reply_userdata = userdata, "command_async");
+        let command_cstring = command.iter()
+                                     .map(|item| to_cstring(item))
+                                     .collect::<Result<Vec<_>>>()
+                                     .command_context(name)?;
         let mut command_pointers: Vec<_> = command_cstring.iter()
                                                           .map(|item| item.as_ptr())
                                                           .collect();
         command_pointers.push(ptr::null());
         let ret = unsafe { mpv_command_async(self.handle, userdata,command_pointers.as_mut_ptr())};
 
-        ret_to_result(ret, ())
+        ret_to_result(ret, ()).command_context(name)
+    }
+
+    /// Send a message to the client named `target`, which will receive it as a
+    /// `ClientMessage` event. For a Lua script, the client name is the name of the script.
+    ///
+    /// This is a shortcut for the `script-message-to` command.
+    pub fn send_client_message(&mut self, target: &str, args: &[&str]) -> Result<()> {
+        let mut command = Vec::with_capacity(args.len() + 2);
+        command.push("script-message-to");
+        command.push(target);
+        command.extend_from_slice(args);
+        self.command(&command)
+    }
+
+    /// Send a command synchronously, using a `Node` for the arguments and the result
+    ///
+    /// The command can either be a `Node::Array` of arguments, the first one being
+    /// the name of the command, or a `Node::Map` with named arguments, in which case
+    /// the name of the command is given with the "name" key.
+    ///
+    /// Unlike `command`, the arguments keep their type and the result of the command
+    /// is returned. Most commands return `Node::None`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use std::collections::BTreeMap;
+    /// # use mpv::Node;
+    /// # let mut mpv = mpv::MpvHandlerBuilder::new().unwrap().build().unwrap();
+    /// let mut args = BTreeMap::new();
+    /// args.insert("name".to_owned(), Node::from("seek"));
+    /// args.insert("target".to_owned(), Node::from(10.0));
+    /// args.insert("flags".to_owned(), Node::from("absolute"));
+    /// mpv.command_node(&Node::Map(args)).expect("Failed to seek");
+    /// ```
+    pub fn command_node(&mut self, command: &Node) -> Result<Node> {
+        let name = node_command_name(command);
+        mpv_span!("command_node", command = name);
+        let mut storage = MpvNodeStorage::default();
+        let mut args = storage.build(command).command_context(name)?;
+        let mut result = mpv_node::default();
+        let ret = unsafe { mpv_command_node(self.handle, &mut args, &mut result) };
+        let node = unsafe { Node::from_mpv_node(&result) };
+        unsafe { mpv_free_node_contents(&mut result) };
+        ret_to_result(ret, node).command_context(name)
+    }
+
+    /// Send a command asynchronously, using a `Node` for the arguments.
+    ///
+    /// The returned future is resolved with the result of the command.
+    pub fn command_node_async(&mut self, command: &Node) -> AsyncReply<Node> {
+        let userdata = self.register_reply();
+        match self.command_node_async_raw(command, userdata) {
+            Ok(()) => {
+                let name = node_command_name(command);
+                AsyncReply::waiting(self.replies.clone(), userdata, name, command_reply)
+            },
+            Err(e) => self.reply_failed(userdata, e),
+        }
+    }
+
+    /// Send a command asynchronously, using a `Node` for the arguments.
+    ///
+    /// The result will be returned as the `Node` of `Event::CommandReply`, with the
+    /// given userdata.
+    pub fn command_node_async_with_userdata(&mut self, command: &Node, userdata: u32) -> Result<()> {
+        self.command_node_async_raw(command, userdata as u64)
+    }
+
+    fn command_node_async_raw(&mut self, command: &Node, userdata: u64) -> Result<()> {
+        let name = node_command_name(command);
+        mpv_event!(command = name, reply_userdata = userdata, "command_node_async");
+        let mut storage = MpvNodeStorage::default();
+        let mut args = storage.build(command).command_context(name)?;
+        let ret = unsafe { mpv_command_node_async(self.handle, userdata, &mut args) };
+        ret_to_result(ret, ()).command_context(name)
+    }
+
+    pub(crate) fn reply_dispatcher(&self) -> Arc<Mutex<ReplyDispatcher>> {
+        self.replies.clone()
+    }
+
+    fn register_reply(&self) -> u64 {
+        self.replies.lock().unwrap().register()
+    }
+
+    /// Returns an `AsyncReply` for a request which could not be sent to libmpv
+    fn reply_failed<T>(&self, userdata: u64, error: Error) -> AsyncReply<T> {
+        self.replies.lock().unwrap().forget(userdata);
+        AsyncReply::failed(error)
     }
 
     /// Returns an Event if there is an Event available. Returns None if the event pool is empty.
@@ -343,35 +511,133 @@ impl MpvHandler {
     /// It is still necessary to empty the event pool even if you don't use the events, since
     /// the event pool is not limited and will be full if you don't empty it.
     ///
+    /// The returned `Event` owns its data, so it stays valid after the next call to `wait_event`.
+    ///
+    /// The events which are not returned, such as the replies to the future-returning
+    /// functions, do not end the wait : `None` is only returned once `timeout` has elapsed.
+    ///
     /// # Panics
     ///
     /// Will panic if a null pointer is received from the libmpv API (should never happen)
 
-    pub fn wait_event<'a>(&mut self,timeout:f64) -> Option<Event<'a>> {
-        let event = unsafe {
-            let ptr = mpv_wait_event(self.handle, timeout);
-            if ptr.is_null() {
-                panic!("Unexpected null ptr from mpv_wait_event");
+    pub fn wait_event(&mut self,timeout:f64) -> Option<Event> {
+        // a negative timeout waits forever, and a too large one is as good as forever
+        let deadline = Duration::try_from_secs_f64(timeout)
+                               .ok()
+                               .and_then(|timeout| Instant::now().checked_add(timeout));
+        let mut timeout = timeout;
+        loop {
+            let event = unsafe {
+                let ptr = mpv_wait_event(self.handle, timeout);
+                if ptr.is_null() {
+                    panic!("Unexpected null ptr from mpv_wait_event");
+                }
+                *ptr
+            };
+            let converted_event = to_event(event.event_id,
+                                           event.error,
+                                           event.reply_userdata,
+                                           event.data)?;
+            mpv_event!(event_id = %event.event_id, reply_userdata = event.reply_userdata, "event");
+            match converted_event {
+                Event::LogMessage { ref prefix, ref text, log_level, .. }
+                        if self.forward_logs => {
+                    forward_log_message(prefix, log_level, text);
+                },
+                Event::PropertyChange { change, .. }
+                        if Some(event.reply_userdata) == self.metadata_observer => {
+                    let metadata = match change {
+                        Format::Node(node) => metadata_from_node(&node).ok(),
+                        _ => None,
+                    };
+                    return Some(Event::MetadataUpdate(metadata));
+                },
+                // the tags are sent by the observer instead, without it the event would
+                // look like the tags have been cleared
+                Event::MetadataUpdate(None) if self.metadata_observer.is_some() => {},
+                // replies to the future-returning functions are not returned
+                converted_event if event.reply_userdata >= INTERNAL_USERDATA_START => {
+                    self.replies.lock().unwrap().dispatch(event.reply_userdata, converted_event);
+                },
+                converted_event => return Some(converted_event),
             }
-            *ptr
-        };
-        to_event(event.event_id,
-                 event.error,
-                 event.reply_userdata,
-                 event.data)
+            // the event has not been returned, wait for the remaining time only
+            if let Some(deadline) = deadline {
+                timeout = deadline.saturating_duration_since(Instant::now()).as_secs_f64();
+            }
+        }
+    }
+
+    ///
+    /// Set a callback which is called by libmpv whenever new events are available, so that
+    /// `wait_event` can be called from an external event loop only when needed (typically
+    /// with a 0 timeout, until it returns `None`).
+    ///
+    /// The callback replaces the previous one, if any. It is called from an arbitrary thread
+    /// of libmpv, and must return quickly : calling any function of the mpv-rs API from it
+    /// is not allowed, you should only wake up your own event loop.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use std::sync::mpsc::channel;
+    /// # let mut mpv = mpv::MpvHandlerBuilder::new().unwrap().build().unwrap();
+    /// let (sender, receiver) = channel();
+    /// let sender = std::sync::Mutex::new(sender);
+    /// mpv.set_wakeup_callback(Box::new(move || {
+    ///     let _ = sender.lock().unwrap().send(());
+    /// }));
+    /// while receiver.recv().is_ok() {
+    ///     while let Some(event) = mpv.wait_event(0.0) {
+    ///         println!("RECEIVED EVENT : {:?}", event);
+    ///     }
+    /// }
+    /// ```
+    pub fn set_wakeup_callback(&mut self, callback: WakeupCallback) {
+        let mut callback = Box::new(callback);
+        unsafe {
+            mpv_set_wakeup_callback(self.handle,
+                                    Some(MpvHandler::call_wakeup_callback),
+                                    callback.as_mut() as *mut WakeupCallback as *mut c_void);
+        }
+        // libmpv does not call the previous callback anymore, it can be dropped
+        self.wakeup_callback = Some(callback);
+    }
+
+    /// Remove the callback set with `set_wakeup_callback`, if any
+    pub fn clear_wakeup_callback(&mut self) {
+        unsafe {
+            mpv_set_wakeup_callback(self.handle, None, ptr::null_mut());
+        }
+        self.wakeup_callback = None;
+    }
+
+    unsafe extern "C" fn call_wakeup_callback(d: *mut c_void) {
+        let callback = d as *const WakeupCallback;
+        assert!(!callback.is_null());
+        (*callback)();
+    }
+
+    /// Interrupt the current `wait_event` call, which will return `None`, even if it
+    /// was called from another thread.
+    ///
+    /// If no `wait_event` call is running, the next one will return immediately.
+    pub fn wakeup(&self) {
+        unsafe { mpv_wakeup(self.handle) }
     }
 
     /// Observe a property change. The property change will be returned via an Event PropertyChange
-    #[cfg_attr(feature = "clippy", allow(temporary_cstring_as_ptr))]
+    ///
+    /// See also `watch_property`, which returns the changes through a typed `ObservedProperty`.
     pub fn observe_property<T:MpvFormat>(&mut self,name:&str,userdata:u32) -> Result<()>{
         let userdata = userdata as ::std::os::raw::c_ulong;
+        let c_name = to_cstring(name).property_context(name)?;
         let ret = unsafe {
             mpv_observe_property(self.handle,
                                  userdata,
-                                 ffi::CString::new(name).unwrap().as_ptr(),
+                                 c_name.as_ptr(),
                                  T::get_mpv_format())
         };
-        ret_to_result(ret,())
+        ret_to_result(ret,()).property_context(name)
     }
 
     /// Unobserve a previously observed property change
@@ -384,6 +650,100 @@ impl MpvHandler {
         ret_to_result(ret,())
     }
 
+    ///
+    /// Receive the given kind of event again, after a call to `disable_event`.
+    /// Every event is enabled by default.
+    pub fn enable_event(&mut self, event: MpvEventId) -> Result<()> {
+        request_event(self.handle, event, true)
+    }
+
+    ///
+    /// Stop receiving the given kind of event, for instance the frequent `MPV_EVENT_TICK`.
+    ///
+    /// Disabling the replies (such as `MPV_EVENT_GET_PROPERTY_REPLY`) or `MPV_EVENT_PROPERTY_CHANGE`
+    /// prevents the futures and the observed properties from receiving them.
+    /// `MPV_EVENT_SHUTDOWN` cannot be disabled.
+    pub fn disable_event(&mut self, event: MpvEventId) -> Result<()> {
+        request_event(self.handle, event, false)
+    }
+
+    ///
+    /// Enable the reception of the log messages of mpv as `Event::LogMessage`, for messages
+    /// of `min_level` or more important. `MPV_LOG_LEVEL_NONE` disables them.
+    pub fn request_log_messages(&mut self, min_level: LogLevel) -> Result<()> {
+        let c_level = to_cstring(min_level.as_str())?;
+        let ret = unsafe { mpv_request_log_messages(self.handle, c_level.as_ptr()) };
+        ret_to_result(ret, ())
+    }
+
+    ///
+    /// Send the log messages of mpv of `min_level` or more important to the `log` crate,
+    /// instead of returning them as `Event::LogMessage`. The prefix of the message (such
+    /// as "cplayer" or "ffmpeg") is used as the target, and the level is converted with
+    /// `LogLevel::to_log_level`.
+    ///
+    /// Messages are sent from `wait_event`, which must still be called regularly.
+    /// With the "tracing" feature, they are also sent as tracing events, with the prefix
+    /// and the level of the message as fields.
+    /// `MPV_LOG_LEVEL_NONE` disables the forwarding.
+    pub fn forward_log_messages(&mut self, min_level: LogLevel) -> Result<()> {
+        self.request_log_messages(min_level)?;
+        self.forward_logs = !matches!(min_level, LogLevel::MPV_LOG_LEVEL_NONE);
+        Ok(())
+    }
+
+    ///
+    /// Observe the tags of the current file, which are then given by `Event::MetadataUpdate`
+    /// every time they change, instead of `Event::PropertyChange`.
+    ///
+    /// Does nothing if the tags are already observed.
+    pub fn observe_metadata(&mut self) -> Result<()> {
+        if self.metadata_observer.is_some() {
+            return Ok(());
+        }
+        let userdata = self.replies.lock().unwrap().reserve();
+        let ret = unsafe {
+            mpv_observe_property(self.handle,
+                                 userdata,
+                                 b"metadata\0".as_ptr() as *const _,
+                                 <Node as MpvFormat>::get_mpv_format())
+        };
+        ret_to_result(ret, ()).property_context("metadata")?;
+        self.metadata_observer = Some(userdata);
+        Ok(())
+    }
+
+    /// Stop observing the tags of the current file, see `observe_metadata`
+    pub fn unobserve_metadata(&mut self) -> Result<()> {
+        match self.metadata_observer.take() {
+            Some(userdata) => {
+                let ret = unsafe { mpv_unobserve_property(self.handle, userdata) };
+                ret_to_result(ret, ())
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Returns what happens to the player when this handle is dropped
+    pub fn ownership(&self) -> Ownership {
+        self.ownership
+    }
+
+    /// Change what happens to the player when this handle is dropped
+    pub fn set_ownership(&mut self, ownership: Ownership) {
+        self.ownership = ownership;
+    }
+
+    ///
+    /// Destroy this handle without destroying the player, whatever its ownership : the
+    /// playback goes on if another client still exists.
+    ///
+    /// If this handle was the last client, the player is destroyed anyway, as with
+    /// `Ownership::Client`.
+    pub fn detach(mut self) {
+        self.ownership = Ownership::Client;
+    }
+
     /// Get the raw pointer for the mpv_handle. Use with care.
     pub fn raw(&self) -> *mut mpv_handle {
         self.handle
@@ -397,19 +757,35 @@ impl MpvHandler {
     }
 }
 
-impl Drop for MpvHandlerWithGl {
-    fn drop(&mut self) {
-        unsafe {
-            // careful : always uninit gl before terminate_destroy mpv
-            mpv_opengl_cb_uninit_gl(self.gl_context);
-        }
-    }
+/// Enables or disables an event, with `mpv_request_event`
+fn request_event(handle: *mut mpv_handle, event: MpvEventId, enable: bool) -> Result<()> {
+    let ret = unsafe { mpv_request_event(handle, event, enable as c_int) };
+    ret_to_result(ret, ())
+}
+
+/// Returns the name of a command sent as a `Node`, to give some context to errors
+fn node_command_name(command: &Node) -> &str {
+    let name = match *command {
+        Node::Array(ref args) => args.first(),
+        Node::Map(ref args) => args.get("name"),
+        _ => None,
+    };
+    name.and_then(|name| name.as_str()).unwrap_or("")
 }
 
 impl Drop for MpvHandler {
     fn drop(&mut self) {
+        if self.wakeup_callback.is_some() {
+            self.clear_wakeup_callback();
+        }
+        if let Ok(mut replies) = self.replies.lock() {
+            replies.close();
+        }
         unsafe {
-            mpv_terminate_destroy(self.handle);
+            match self.ownership {
+                Ownership::Primary => mpv_terminate_destroy(self.handle),
+                Ownership::Client => mpv_detach_destroy(self.handle),
+            }
         }
     }
 }
@@ -0,0 +1,65 @@
+use mpv_gen::LogLevel;
+
+use log;
+
+impl LogLevel {
+    /// Returns the name of the level, as expected by `MpvHandler::request_log_messages`
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            LogLevel::MPV_LOG_LEVEL_NONE => "no",
+            LogLevel::MPV_LOG_LEVEL_FATAL => "fatal",
+            LogLevel::MPV_LOG_LEVEL_ERROR => "error",
+            LogLevel::MPV_LOG_LEVEL_WARN => "warn",
+            LogLevel::MPV_LOG_LEVEL_INFO => "info",
+            LogLevel::MPV_LOG_LEVEL_V => "v",
+            LogLevel::MPV_LOG_LEVEL_DEBUG => "debug",
+            LogLevel::MPV_LOG_LEVEL_TRACE => "trace",
+        }
+    }
+
+    /// Returns the matching level of the `log` crate, or `None` for `MPV_LOG_LEVEL_NONE`
+    pub fn to_log_level(&self) -> Option<log::Level> {
+        match *self {
+            LogLevel::MPV_LOG_LEVEL_NONE => None,
+            LogLevel::MPV_LOG_LEVEL_FATAL |
+            LogLevel::MPV_LOG_LEVEL_ERROR => Some(log::Level::Error),
+            LogLevel::MPV_LOG_LEVEL_WARN => Some(log::Level::Warn),
+            LogLevel::MPV_LOG_LEVEL_INFO => Some(log::Level::Info),
+            LogLevel::MPV_LOG_LEVEL_V |
+            LogLevel::MPV_LOG_LEVEL_DEBUG => Some(log::Level::Debug),
+            LogLevel::MPV_LOG_LEVEL_TRACE => Some(log::Level::Trace),
+        }
+    }
+}
+
+/// Sends a log message of mpv to the `log` crate, with its prefix (such as "cplayer")
+/// as target, and with the "tracing" feature as a tracing event as well
+pub fn forward_log_message(prefix: &str, log_level: LogLevel, text: &str) {
+    // mpv terminates every message with a newline
+    let text = text.trim_end_matches('\n');
+    if let Some(level) = log_level.to_log_level() {
+        ::log::log!(target: prefix, level, "{}", text);
+        #[cfg(feature = "tracing")]
+        trace_log_message(prefix, log_level, level, text);
+    }
+}
+
+/// Sends a log message of mpv as a tracing event, with its prefix and its level as fields
+#[cfg(feature = "tracing")]
+fn trace_log_message(prefix: &str, log_level: LogLevel, level: log::Level, text: &str) {
+    use tracing::Level;
+
+    // the level of a tracing event must be a constant
+    macro_rules! forward {
+        ($level:expr) => {
+            ::tracing::event!($level, prefix, level = log_level.as_str(), "{}", text)
+        }
+    }
+    match level {
+        log::Level::Error => forward!(Level::ERROR),
+        log::Level::Warn => forward!(Level::WARN),
+        log::Level::Info => forward!(Level::INFO),
+        log::Level::Debug => forward!(Level::DEBUG),
+        log::Level::Trace => forward!(Level::TRACE),
+    }
+}
@@ -0,0 +1,49 @@
+use mpv_handler::MpvHandler;
+use mpv_node::Node;
+use mpv_error::*;
+
+use std::collections::BTreeMap;
+
+///
+/// Reads the tags of a metadata property, such as `metadata`, into a map.
+///
+/// Tags which are not strings are skipped, mpv only sends strings for these properties.
+pub fn metadata_from_node(node: &Node) -> Result<BTreeMap<String, String>> {
+    match *node {
+        Node::Map(ref tags) => {
+            Ok(tags.iter()
+                   .filter_map(|(key, value)| value.as_str().map(|value| (key.clone(), value.to_owned())))
+                   .collect())
+        },
+        _ => Err(Error::FormatMismatch {
+            expected: "map",
+            found: node.format_name(),
+        }),
+    }
+}
+
+impl MpvHandler {
+    fn metadata_property(&self, name: &str) -> Result<BTreeMap<String, String>> {
+        let node: Node = self.get_property(name)?;
+        metadata_from_node(&node).property_context(name)
+    }
+
+    ///
+    /// Returns the tags of the current file, such as "title" or "artist". For streams,
+    /// this includes the ICY tags such as "icy-title".
+    ///
+    /// To be notified when they change, see `observe_metadata`.
+    pub fn metadata(&self) -> Result<BTreeMap<String, String>> {
+        self.metadata_property("metadata")
+    }
+
+    /// Returns the tags of the current file, filtered by the `display-tags` option
+    pub fn filtered_metadata(&self) -> Result<BTreeMap<String, String>> {
+        self.metadata_property("filtered-metadata")
+    }
+
+    /// Returns the tags of the current chapter
+    pub fn chapter_metadata(&self) -> Result<BTreeMap<String, String>> {
+        self.metadata_property("chapter-metadata")
+    }
+}
@@ -0,0 +1,366 @@
+use std::collections::BTreeMap;
+use std::ffi::{CStr, CString};
+use std::{ptr, slice};
+
+use mpv_error::{Error, Result};
+use mpv_gen::{MpvFormat as MpvInternalFormat, mpv_node, mpv_node_list, mpv_byte_array};
+use ::std::os::raw::{c_char, c_int, c_void};
+
+///
+/// An owned representation of a `mpv_node`.
+///
+/// This is the type to use when a property or a command returns structured data,
+/// such as `track-list`, `playlist`, `chapter-list` or `metadata`.
+///
+/// Every string and list is copied out of the libmpv memory, so a `Node` can be kept
+/// around for as long as you want.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Node {
+    /// `MPV_FORMAT_NONE`, sent for instance when a property is not available
+    None,
+    /// `MPV_FORMAT_STRING`
+    String(String),
+    /// `MPV_FORMAT_FLAG`
+    Flag(bool),
+    /// `MPV_FORMAT_INT64`
+    Int64(i64),
+    /// `MPV_FORMAT_DOUBLE`
+    Double(f64),
+    /// `MPV_FORMAT_NODE_ARRAY`
+    Array(Vec<Node>),
+    /// `MPV_FORMAT_NODE_MAP`
+    Map(BTreeMap<String, Node>),
+    /// `MPV_FORMAT_BYTE_ARRAY`
+    ByteArray(Vec<u8>),
+}
+
+impl Node {
+    /// Returns the string if this node is a `Node::String`
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Node::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the boolean if this node is a `Node::Flag`
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Node::Flag(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer if this node is a `Node::Int64`
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Node::Int64(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a f64 if this node is a `Node::Double` or a `Node::Int64`
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Node::Double(d) => Some(d),
+            Node::Int64(i) => Some(i as f64),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements if this node is a `Node::Array`
+    pub fn as_array(&self) -> Option<&[Node]> {
+        match *self {
+            Node::Array(ref a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns the key/value pairs if this node is a `Node::Map`
+    pub fn as_map(&self) -> Option<&BTreeMap<String, Node>> {
+        match *self {
+            Node::Map(ref m) => Some(m),
+            _ => None,
+        }
+    }
+
+    /// Returns the bytes if this node is a `Node::ByteArray`
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match *self {
+            Node::ByteArray(ref b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Shortcut to retrieve the value of `key` if this node is a `Node::Map`
+    pub fn get(&self, key: &str) -> Option<&Node> {
+        self.as_map().and_then(|m| m.get(key))
+    }
+
+    /// Reads the required field `key` of a map received from mpv, with a conversion
+    /// such as `Node::as_f64`
+    pub(crate) fn required<'a, T>(&'a self, key: &'static str, convert: fn(&'a Node) -> Option<T>)
+                                  -> Result<T> {
+        match *self {
+            Node::Map(ref map) => map.get(key).and_then(convert).ok_or(Error::MissingField(key)),
+            _ => Err(Error::FormatMismatch {
+                expected: "map",
+                found: self.format_name(),
+            }),
+        }
+    }
+
+    /// Returns a human readable name of the kind of this node, such as "string" or "map"
+    pub fn format_name(&self) -> &'static str {
+        match *self {
+            Node::None => "none",
+            Node::String(_) => "string",
+            Node::Flag(_) => "flag",
+            Node::Int64(_) => "int64",
+            Node::Double(_) => "double",
+            Node::Array(_) => "array",
+            Node::Map(_) => "map",
+            Node::ByteArray(_) => "byte array",
+        }
+    }
+
+    pub fn get_mpv_format(&self) -> MpvInternalFormat {
+        match *self {
+            Node::None => MpvInternalFormat::MPV_FORMAT_NONE,
+            Node::String(_) => MpvInternalFormat::MPV_FORMAT_STRING,
+            Node::Flag(_) => MpvInternalFormat::MPV_FORMAT_FLAG,
+            Node::Int64(_) => MpvInternalFormat::MPV_FORMAT_INT64,
+            Node::Double(_) => MpvInternalFormat::MPV_FORMAT_DOUBLE,
+            Node::Array(_) => MpvInternalFormat::MPV_FORMAT_NODE_ARRAY,
+            Node::Map(_) => MpvInternalFormat::MPV_FORMAT_NODE_MAP,
+            Node::ByteArray(_) => MpvInternalFormat::MPV_FORMAT_BYTE_ARRAY,
+        }
+    }
+
+    ///
+    /// Copies a `mpv_node` owned by libmpv into a new `Node`.
+    ///
+    /// This is used internally by the mpv-rs crate, you probably should not be using this.
+    /// The given node is not freed.
+    ///
+    /// # Safety
+    ///
+    /// `node` must point to a valid `mpv_node`, such as one filled by libmpv.
+    pub unsafe fn from_mpv_node(node: *const mpv_node) -> Node {
+        let mut node = *node;
+        match node.format {
+            MpvInternalFormat::MPV_FORMAT_STRING |
+            MpvInternalFormat::MPV_FORMAT_OSD_STRING => {
+                Node::String(cstr_to_string(*node.u.string()))
+            },
+            MpvInternalFormat::MPV_FORMAT_FLAG => Node::Flag(*node.u.flag() != 0),
+            MpvInternalFormat::MPV_FORMAT_INT64 => Node::Int64(*node.u.int64()),
+            MpvInternalFormat::MPV_FORMAT_DOUBLE => Node::Double(*node.u.double_()),
+            MpvInternalFormat::MPV_FORMAT_NODE_ARRAY => {
+                Node::from_mpv_node_list(*node.u.list(), MpvInternalFormat::MPV_FORMAT_NODE_ARRAY)
+            },
+            MpvInternalFormat::MPV_FORMAT_NODE_MAP => {
+                Node::from_mpv_node_list(*node.u.list(), MpvInternalFormat::MPV_FORMAT_NODE_MAP)
+            },
+            MpvInternalFormat::MPV_FORMAT_BYTE_ARRAY => {
+                Node::ByteArray(Node::bytes_from_mpv_byte_array(*node.u.ba()))
+            },
+            MpvInternalFormat::MPV_FORMAT_NONE |
+            MpvInternalFormat::MPV_FORMAT_NODE => Node::None,
+        }
+    }
+
+    ///
+    /// Copies the content of a `mpv_byte_array` owned by libmpv.
+    ///
+    /// This is used internally by the mpv-rs crate, you probably should not be using this.
+    ///
+    /// # Safety
+    ///
+    /// `byte_array` must either be null or point to a valid `mpv_byte_array`.
+    pub unsafe fn bytes_from_mpv_byte_array(byte_array: *const mpv_byte_array) -> Vec<u8> {
+        if byte_array.is_null() || (*byte_array).data.is_null() {
+            Vec::new()
+        } else {
+            slice::from_raw_parts((*byte_array).data as *const u8, (*byte_array).size as usize)
+                .to_vec()
+        }
+    }
+
+    ///
+    /// Copies a `mpv_node_list` owned by libmpv into either a `Node::Array` or a `Node::Map`,
+    /// depending on the given format.
+    ///
+    /// This is used internally by the mpv-rs crate, you probably should not be using this.
+    ///
+    /// # Safety
+    ///
+    /// `list` must either be null or point to a valid `mpv_node_list`.
+    pub unsafe fn from_mpv_node_list(list: *const mpv_node_list, format: MpvInternalFormat) -> Node {
+        let is_map = matches!(format, MpvInternalFormat::MPV_FORMAT_NODE_MAP);
+        if list.is_null() || (*list).num <= 0 {
+            return if is_map { Node::Map(BTreeMap::new()) } else { Node::Array(Vec::new()) };
+        }
+        let num = (*list).num as usize;
+        let values = slice::from_raw_parts((*list).values, num);
+        if is_map {
+            let keys = slice::from_raw_parts((*list).keys, num);
+            Node::Map(keys.iter()
+                          .zip(values.iter())
+                          .map(|(key, value)| (cstr_to_string(*key), Node::from_mpv_node(value)))
+                          .collect())
+        } else {
+            Node::Array(values.iter().map(|value| Node::from_mpv_node(value)).collect())
+        }
+    }
+}
+
+/// Reads every element of a list received from mpv, such as `track-list`, with `from_node`
+pub(crate) fn list_from_node<T, F>(node: &Node, from_node: F) -> Result<Vec<T>>
+    where F: Fn(&Node) -> Result<T>
+{
+    match *node {
+        Node::Array(ref elements) => elements.iter().map(from_node).collect(),
+        _ => Err(Error::FormatMismatch {
+            expected: "array",
+            found: node.format_name(),
+        }),
+    }
+}
+
+impl<'a> From<&'a str> for Node {
+    fn from(s: &'a str) -> Node {
+        Node::String(s.to_owned())
+    }
+}
+
+impl From<String> for Node {
+    fn from(s: String) -> Node {
+        Node::String(s)
+    }
+}
+
+impl From<bool> for Node {
+    fn from(b: bool) -> Node {
+        Node::Flag(b)
+    }
+}
+
+impl From<i64> for Node {
+    fn from(i: i64) -> Node {
+        Node::Int64(i)
+    }
+}
+
+impl From<f64> for Node {
+    fn from(d: f64) -> Node {
+        Node::Double(d)
+    }
+}
+
+impl From<Vec<Node>> for Node {
+    fn from(array: Vec<Node>) -> Node {
+        Node::Array(array)
+    }
+}
+
+impl From<BTreeMap<String, Node>> for Node {
+    fn from(map: BTreeMap<String, Node>) -> Node {
+        Node::Map(map)
+    }
+}
+
+impl From<Vec<u8>> for Node {
+    fn from(bytes: Vec<u8>) -> Node {
+        Node::ByteArray(bytes)
+    }
+}
+
+unsafe fn cstr_to_string(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(ptr).to_string_lossy().into_owned()
+    }
+}
+
+///
+/// Holds every allocation a `mpv_node` built from a `Node` points to.
+///
+/// The `mpv_node` returned by `build` is only valid as long as both this storage and
+/// the original `Node` are alive.
+// the lists are boxed so that their address does not change when the Vec grows
+#[allow(clippy::vec_box)]
+#[derive(Default)]
+pub struct MpvNodeStorage {
+    strings: Vec<CString>,
+    keys: Vec<Vec<*mut c_char>>,
+    values: Vec<Vec<mpv_node>>,
+    lists: Vec<Box<mpv_node_list>>,
+    byte_arrays: Vec<Box<mpv_byte_array>>,
+}
+
+impl MpvNodeStorage {
+    pub fn build(&mut self, node: &Node) -> Result<mpv_node> {
+        let mut mpv_node = mpv_node {
+            u: Default::default(),
+            format: node.get_mpv_format(),
+        };
+        unsafe {
+            match *node {
+                Node::None => {},
+                Node::String(ref s) => {
+                    *mpv_node.u.string() = self.push_string(s)?;
+                },
+                Node::Flag(b) => {
+                    *mpv_node.u.flag() = b as c_int;
+                },
+                Node::Int64(i) => {
+                    *mpv_node.u.int64() = i;
+                },
+                Node::Double(d) => {
+                    *mpv_node.u.double_() = d;
+                },
+                Node::Array(ref array) => {
+                    let values = array.iter().map(|n| self.build(n)).collect::<Result<Vec<_>>>()?;
+                    *mpv_node.u.list() = self.push_list(values, ptr::null_mut());
+                },
+                Node::Map(ref map) => {
+                    let keys = map.keys().map(|k| self.push_string(k)).collect::<Result<Vec<_>>>()?;
+                    let values = map.values().map(|n| self.build(n)).collect::<Result<Vec<_>>>()?;
+                    self.keys.push(keys);
+                    let keys_ptr = self.keys.last_mut().unwrap().as_mut_ptr();
+                    *mpv_node.u.list() = self.push_list(values, keys_ptr);
+                },
+                Node::ByteArray(ref bytes) => {
+                    let mut byte_array = Box::new(mpv_byte_array {
+                        data: bytes.as_ptr() as *mut c_void,
+                        size: bytes.len() as _,
+                    });
+                    *mpv_node.u.ba() = byte_array.as_mut() as *mut mpv_byte_array;
+                    self.byte_arrays.push(byte_array);
+                },
+            }
+        }
+        Ok(mpv_node)
+    }
+
+    fn push_string(&mut self, s: &str) -> Result<*mut c_char> {
+        let string = CString::new(s)?;
+        let ptr = string.as_ptr() as *mut c_char;
+        self.strings.push(string);
+        Ok(ptr)
+    }
+
+    fn push_list(&mut self, mut values: Vec<mpv_node>, keys: *mut *mut c_char) -> *mut mpv_node_list {
+        let mut list = Box::new(mpv_node_list {
+            num: values.len() as c_int,
+            values: values.as_mut_ptr(),
+            keys,
+        });
+        let list_ptr = list.as_mut() as *mut mpv_node_list;
+        self.values.push(values);
+        self.lists.push(list);
+        list_ptr
+    }
+}
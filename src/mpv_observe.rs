@@ -0,0 +1,128 @@
+use mpv_gen::{mpv_handle, mpv_observe_property, mpv_unobserve_property};
+use mpv_async::ReplyDispatcher;
+use mpv_enums::{Format, MpvFormatGet};
+use mpv_handler::MpvHandler;
+use mpv_error::*;
+
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{sync_channel, Receiver, TryIter};
+
+///
+/// A property observed with `MpvHandler::watch_property`, which receives the values of
+/// the property as they change.
+///
+/// Changes are received by `MpvHandler::wait_event` (or an `EventStream`), and are not
+/// returned as `Event::PropertyChange`. A value is `None` when the property is not available,
+/// for instance `time-pos` when nothing is playing.
+///
+/// The property is unobserved when this struct is dropped.
+#[derive(Debug)]
+pub struct ObservedProperty<T> {
+    name: String,
+    handle: *mut mpv_handle,
+    userdata: u64,
+    dispatcher: Arc<Mutex<ReplyDispatcher>>,
+    latest: Arc<Mutex<Option<T>>>,
+    changes: Receiver<Option<T>>,
+}
+
+/// The number of values kept for `ObservedProperty::changes`, so that they do not pile up
+/// when only `latest` is used
+pub const MAX_PENDING_CHANGES: usize = 64;
+
+impl<T> ObservedProperty<T> {
+    /// Returns the name of the observed property
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the last value received, or `None` if the property is not available
+    /// or no value has been received yet
+    pub fn latest(&self) -> Option<T> where T: Clone {
+        self.latest.lock().unwrap().clone()
+    }
+
+    ///
+    /// Returns an iterator over the values received since the last call, without blocking.
+    ///
+    /// libmpv coalesces changes, so some intermediate values may never be received.
+    /// At most `MAX_PENDING_CHANGES` values are kept between two calls, the next ones
+    /// being dropped (`latest()` is still updated).
+    ///
+    /// # Example
+    /// ```no_run
+    /// # let mut mpv = mpv::MpvHandlerBuilder::new().unwrap().build().unwrap();
+    /// let pause = mpv.watch_property::<bool>("pause").expect("Failed to observe pause");
+    /// loop {
+    ///     while let Some(event) = mpv.wait_event(0.1) {
+    ///         println!("RECEIVED EVENT : {:?}", event);
+    ///     }
+    ///     for paused in pause.changes() {
+    ///         println!("pause changed to {:?}", paused);
+    ///     }
+    /// }
+    /// ```
+    pub fn changes(&self) -> TryIter<'_, Option<T>> {
+        self.changes.try_iter()
+    }
+}
+
+impl<T> Drop for ObservedProperty<T> {
+    fn drop(&mut self) {
+        if let Ok(mut dispatcher) = self.dispatcher.lock() {
+            // the handle is only valid while the dispatcher is not closed,
+            // and the lock keeps the MpvHandler from being destroyed meanwhile
+            if dispatcher.remove_observer(self.userdata) {
+                unsafe { mpv_unobserve_property(self.handle, self.userdata) };
+            }
+        }
+    }
+}
+
+impl MpvHandler {
+    ///
+    /// Observe a property, and receive its values through the returned `ObservedProperty`
+    /// instead of `Event::PropertyChange`.
+    ///
+    /// The current value of the property is received right after this call, as a first change.
+    pub fn watch_property<T>(&mut self, name: &str) -> Result<ObservedProperty<T>>
+        where T: MpvFormatGet + Clone + Send + 'static
+    {
+        let c_name = to_cstring(name).property_context(name)?;
+        let latest = Arc::new(Mutex::new(None));
+        let (sender, changes) = sync_channel(MAX_PENDING_CHANGES);
+        let observer_latest = latest.clone();
+        let sender = Mutex::new(sender);
+        let dispatcher = self.reply_dispatcher();
+        let userdata = dispatcher.lock().unwrap().register_observer(Box::new(move |change: Format| {
+            // the property is requested with the format of T, so this only fails
+            // when the property is not available, which is also checked first because
+            // a `Node` could be converted from `Format::None`
+            let value = match change {
+                Format::None => None,
+                change => T::from_format(change).ok(),
+            };
+            *observer_latest.lock().unwrap() = value.clone();
+            // dropped if changes() is not called, instead of piling up
+            let _ = sender.lock().unwrap().try_send(value);
+        }));
+        let ret = unsafe {
+            mpv_observe_property(self.raw(),
+                                 userdata,
+                                 c_name.as_ptr(),
+                                 T::get_mpv_format())
+        };
+        if let Err(e) = ret_to_result(ret, ()).property_context(name) {
+            dispatcher.lock().unwrap().remove_observer(userdata);
+            return Err(e);
+        }
+        Ok(ObservedProperty {
+            name: name.to_owned(),
+            handle: self.raw(),
+            userdata,
+            dispatcher,
+            latest,
+            changes,
+        })
+    }
+}
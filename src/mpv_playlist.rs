@@ -0,0 +1,174 @@
+use mpv_handler::MpvHandler;
+use mpv_command::{Command, LoadFileMode};
+use mpv_observe::ObservedProperty;
+use mpv_node::{Node, list_from_node};
+use mpv_error::*;
+
+/// An entry of the playlist, read from the `playlist` property
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlaylistEntry {
+    /// The path or URL given to `loadfile`
+    pub filename: String,
+    /// The title of the entry, if it is known (for instance from a playlist file)
+    pub title: Option<String>,
+    /// Whether this entry is the current one, selected with `playlist-pos`
+    pub current: bool,
+    /// Whether this entry is being played. It can differ from `current` while switching files.
+    pub playing: bool,
+    /// A unique id of the entry, only sent by mpv 0.33 and later
+    pub id: Option<i64>,
+}
+
+impl PlaylistEntry {
+    /// Reads an entry from one element of the `playlist` property
+    pub fn from_node(node: &Node) -> Result<PlaylistEntry> {
+        let filename = node.required("filename", Node::as_str)?;
+        Ok(PlaylistEntry {
+            filename: filename.to_owned(),
+            title: node.get("title").and_then(Node::as_str).map(|title| title.to_owned()),
+            current: node.get("current").and_then(Node::as_bool).unwrap_or(false),
+            playing: node.get("playing").and_then(Node::as_bool).unwrap_or(false),
+            id: node.get("id").and_then(Node::as_i64),
+        })
+    }
+
+    /// Reads the entries from the value of the `playlist` property
+    pub fn list_from_node(node: &Node) -> Result<Vec<PlaylistEntry>> {
+        list_from_node(node, PlaylistEntry::from_node)
+    }
+}
+
+///
+/// A view of the playlist of a `MpvHandler`, created with `MpvHandler::playlist()`.
+///
+/// Entries are designated by their index in the playlist, starting at 0.
+///
+/// # Example
+/// ```no_run
+/// # let mut mpv = mpv::MpvHandlerBuilder::new().unwrap().build().unwrap();
+/// let mut playlist = mpv.playlist();
+/// playlist.append("first.mkv").expect("Failed to append");
+/// playlist.insert(0, "intro.mkv").expect("Failed to insert");
+/// for entry in playlist.entries().expect("Failed to read the playlist") {
+///     println!("{} {}", if entry.current { ">" } else { " " }, entry.filename);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Playlist<'a> {
+    mpv_handler: &'a mut MpvHandler,
+}
+
+impl MpvHandler {
+    /// Returns a view of the playlist of this player
+    pub fn playlist(&mut self) -> Playlist<'_> {
+        Playlist { mpv_handler: self }
+    }
+}
+
+impl<'a> Playlist<'a> {
+    /// Returns the entries of the playlist
+    pub fn entries(&self) -> Result<Vec<PlaylistEntry>> {
+        let node: Node = self.mpv_handler.get_property("playlist")?;
+        PlaylistEntry::list_from_node(&node).property_context("playlist")
+    }
+
+    /// Returns the number of entries of the playlist
+    pub fn len(&self) -> Result<usize> {
+        self.mpv_handler.get_property::<i64>("playlist-count").map(|count| count as usize)
+    }
+
+    /// Returns true if the playlist has no entries
+    pub fn is_empty(&self) -> Result<bool> {
+        self.len().map(|len| len == 0)
+    }
+
+    /// Returns the index of the current entry, if any
+    pub fn current(&self) -> Result<Option<usize>> {
+        let pos = self.mpv_handler.get_property::<i64>("playlist-pos")?;
+        Ok(if pos < 0 { None } else { Some(pos as usize) })
+    }
+
+    /// Play the entry at `index`
+    pub fn play(&mut self, index: usize) -> Result<()> {
+        self.mpv_handler.set_property("playlist-pos", index as i64)
+    }
+
+    /// Append a file or an URL at the end of the playlist
+    pub fn append(&mut self, path: &str) -> Result<()> {
+        self.mpv_handler.run(&Command::load_file(path, LoadFileMode::Append)).map(|_| ())
+    }
+
+    /// Insert a file or an URL in the playlist, at `index`
+    pub fn insert(&mut self, index: usize, path: &str) -> Result<()> {
+        match self.mpv_handler.run(&Command::load_file(path, LoadFileMode::InsertAt(index))) {
+            // mpv older than 0.38 does not know insert-at
+            Err(ref e) if matches!(e.mpv_code(),
+                                   Some(MpvErrorCode::MPV_ERROR_INVALID_PARAMETER)) => {},
+            result => return result.map(|_| ()),
+        }
+        self.append(path)?;
+        // another client may have changed the playlist meanwhile, only move our entry
+        let entries = self.entries()?;
+        match entries.last() {
+            Some(entry) if entry.filename == path => {
+                let last = entries.len() - 1;
+                if index < last {
+                    self.move_entry(last, index)?;
+                }
+                Ok(())
+            },
+            _ => Err(Error::Mpv(MpvErrorCode::MPV_ERROR_COMMAND)).command_context("playlist-move"),
+        }
+    }
+
+    /// Remove the entry at `index`. If it is the current entry, playback goes on with the
+    /// next entry, or stops if it was the last one.
+    pub fn remove(&mut self, index: usize) -> Result<()> {
+        self.mpv_handler.command(&["playlist-remove", &index.to_string()])
+    }
+
+    /// Move the entry at `from`, so that its index becomes `to`
+    pub fn move_entry(&mut self, from: usize, to: usize) -> Result<()> {
+        // playlist-move puts the entry before the entry at its second index
+        let to = if to > from { to + 1 } else { to };
+        self.mpv_handler.command(&["playlist-move", &from.to_string(), &to.to_string()])
+    }
+
+    /// Shuffle the playlist
+    pub fn shuffle(&mut self) -> Result<()> {
+        self.mpv_handler.command(&["playlist-shuffle"])
+    }
+
+    /// Remove every entry of the playlist, except the current one
+    pub fn clear(&mut self) -> Result<()> {
+        self.mpv_handler.command(&["playlist-clear"])
+    }
+
+    /// Observe the playlist, to be notified of its changes
+    pub fn watch(&mut self) -> Result<PlaylistObserver> {
+        Ok(PlaylistObserver { playlist: self.mpv_handler.watch_property("playlist")? })
+    }
+}
+
+///
+/// Receives the entries of the playlist when it changes, created with `Playlist::watch()`.
+///
+/// See `ObservedProperty` for how changes are received.
+#[derive(Debug)]
+pub struct PlaylistObserver {
+    playlist: ObservedProperty<Node>,
+}
+
+impl PlaylistObserver {
+    /// Returns the last entries received
+    pub fn latest(&self) -> Option<Vec<PlaylistEntry>> {
+        self.playlist.latest().and_then(|node| PlaylistEntry::list_from_node(&node).ok())
+    }
+
+    /// Returns an iterator over the playlists received since the last call, without blocking
+    pub fn changes(&self) -> impl Iterator<Item = Vec<PlaylistEntry>> + '_ {
+        self.playlist
+            .changes()
+            .filter_map(|node| node.and_then(|node| PlaylistEntry::list_from_node(&node).ok()))
+    }
+}
@@ -0,0 +1,293 @@
+use mpv_gen::{mpv_render_context, mpv_render_param, MpvRenderParamType,
+              mpv_render_context_create, mpv_render_context_free,
+              mpv_render_context_set_update_callback, mpv_render_context_update,
+              mpv_render_context_render, mpv_render_context_report_swap,
+              mpv_opengl_init_params, mpv_opengl_fbo, mpv_opengl_cb_get_proc_address_fn,
+              MPV_RENDER_API_TYPE_SW, MPV_RENDER_API_TYPE_OPENGL, MPV_RENDER_UPDATE_FRAME};
+use mpv_handler::MpvHandler;
+use mpv_error::*;
+
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::ops::{Deref, DerefMut};
+
+///
+/// This struct is a decorator of `MpvHandler`, and can use all the functions from `MpvHandler`.
+/// It is used when the video must be rendered somewhere else than in a window created by mpv,
+/// through the `render.h` API of libmpv.
+///
+/// It is created by the `MpvHandlerBuilder`, either with `build_with_gl(...)` to draw with
+/// OpenGL, or with `build_with_sw_render()` to render into memory.
+///
+#[derive(Debug)]
+pub struct RenderContext {
+    mpv_handler: MpvHandler,
+    render_context: *mut mpv_render_context,
+    // boxed so that the pointer given to the update callback stays valid when moved
+    update_available: Box<AtomicBool>,
+}
+
+/// The OpenGL flavor of `RenderContext`, created with `MpvHandlerBuilder::build_with_gl(...)`
+pub type MpvHandlerWithGl = RenderContext;
+
+/// The pixel formats supported by the software renderer.
+///
+/// Every format uses 4 bytes per pixel, the `0` being an unused padding byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwFormat {
+    /// `rgb0` : red, green, blue, padding
+    Rgb0,
+    /// `bgr0` : blue, green, red, padding
+    Bgr0,
+    /// `0bgr` : padding, blue, green, red
+    ZeroBgr,
+    /// `0rgb` : padding, red, green, blue
+    ZeroRgb,
+}
+
+impl SwFormat {
+    fn as_bytes(&self) -> &'static [u8] {
+        match *self {
+            SwFormat::Rgb0 => b"rgb0\0",
+            SwFormat::Bgr0 => b"bgr0\0",
+            SwFormat::ZeroBgr => b"0bgr\0",
+            SwFormat::ZeroRgb => b"0rgb\0",
+        }
+    }
+
+    /// Returns the number of bytes used by a single pixel
+    pub fn bytes_per_pixel(&self) -> usize {
+        4
+    }
+}
+
+fn render_param(param_type: MpvRenderParamType, data: *mut c_void) -> mpv_render_param {
+    mpv_render_param {
+        _type: param_type,
+        data,
+    }
+}
+
+fn invalid_parameter() -> Error {
+    Error::Mpv(MpvErrorCode::MPV_ERROR_INVALID_PARAMETER)
+}
+
+impl RenderContext {
+    /// Creates the render context of an initialized `MpvHandler`.
+    ///
+    /// `params` must not contain the terminating `MPV_RENDER_PARAM_INVALID`,
+    /// it is added by this function.
+    pub(crate) fn new(mpv_handler: MpvHandler, mut params: Vec<mpv_render_param>) -> Result<RenderContext> {
+        params.push(render_param(MpvRenderParamType::MPV_RENDER_PARAM_INVALID, ptr::null_mut()));
+        let mut render_context = ptr::null_mut();
+        let ret = unsafe {
+            mpv_render_context_create(&mut render_context, mpv_handler.raw(), params.as_mut_ptr())
+        };
+        ret_to_result(ret, ())?;
+
+        let render_context = RenderContext {
+            mpv_handler,
+            render_context,
+            update_available: Box::new(AtomicBool::new(false)),
+        };
+        unsafe {
+            mpv_render_context_set_update_callback(render_context.render_context,
+                                                   Some(RenderContext::update_draw),
+                                                   render_context.update_available.as_ref()
+                                                       as *const AtomicBool as *mut c_void);
+        }
+        Ok(render_context)
+    }
+
+    /// Creates a render context using the software renderer
+    pub(crate) fn new_sw(mpv_handler: MpvHandler) -> Result<RenderContext> {
+        let params = vec![
+            render_param(MpvRenderParamType::MPV_RENDER_PARAM_API_TYPE,
+                         MPV_RENDER_API_TYPE_SW.as_ptr() as *mut c_void),
+        ];
+        RenderContext::new(mpv_handler, params)
+    }
+
+    /// Creates a render context using OpenGL
+    pub(crate) fn new_gl(mpv_handler: MpvHandler,
+                         get_proc_address: mpv_opengl_cb_get_proc_address_fn,
+                         get_proc_address_ctx: *mut c_void) -> Result<RenderContext> {
+        // get_proc_address is only called during the creation of the context
+        let mut init_params = mpv_opengl_init_params {
+            get_proc_address,
+            get_proc_address_ctx,
+            extra_exts: ptr::null(),
+        };
+        let params = vec![
+            render_param(MpvRenderParamType::MPV_RENDER_PARAM_API_TYPE,
+                         MPV_RENDER_API_TYPE_OPENGL.as_ptr() as *mut c_void),
+            render_param(MpvRenderParamType::MPV_RENDER_PARAM_OPENGL_INIT_PARAMS,
+                         &mut init_params as *mut mpv_opengl_init_params as *mut c_void),
+        ];
+        RenderContext::new(mpv_handler, params)
+    }
+
+    unsafe extern "C" fn update_draw(cb_ctx: *mut c_void) {
+        let update_available = cb_ctx as *const AtomicBool;
+        assert!(!update_available.is_null());
+        (*update_available).store(true, Ordering::Relaxed);
+    }
+
+    /// returns true if mpv asked for a redraw since the last render
+    pub fn is_update_available(&self) -> bool {
+        self.update_available.load(Ordering::Relaxed)
+    }
+
+    ///
+    /// Must be called after mpv asked for a redraw (see `is_update_available()`), before
+    /// rendering. Returns true if a new frame must be rendered.
+    ///
+    /// `is_update_available()` returns false again until mpv asks for another redraw.
+    /// Rendering without a new frame available is allowed, the previous frame is redrawn.
+    pub fn update(&mut self) -> bool {
+        // cleared first, so that a redraw requested while updating is not missed
+        self.update_available.store(false, Ordering::Relaxed);
+        let flags = unsafe { mpv_render_context_update(self.render_context) };
+        flags & MPV_RENDER_UPDATE_FRAME != 0
+    }
+
+    /// Render video
+    ///
+    /// The video will use the full provided framebuffer. Options like "panscan" are
+    /// applied to determine which part of the video should be visible and how the
+    /// video should be scaled. You can change these options at runtime by using the
+    /// mpv property API.
+    ///
+    /// fbo is the framebuffer object to render on. Because the renderer might
+    /// manage multiple FBOs internally for the purpose of video
+    /// postprocessing, it will always bind and unbind FBOs itself. If
+    /// you want mpv to render on the main framebuffer, pass 0.
+    ///
+    /// width is the width of the framebuffer. This is either the video size if the fbo
+    /// parameter is 0, or the allocated size of the texture backing the
+    /// fbo. The renderer will always use the full size of the fbo.
+    ///
+    /// height is the height of the framebuffer. Same as with the w parameter, except
+    /// that this parameter can be negative. In this case, the video
+    /// frame will be rendered flipped, which is usually needed when rendering
+    /// on the main framebuffer.
+    ///
+    /// # Errors
+    ///
+    /// If the external video module has not been configured correctly, libmpv can send various
+    /// errors such as MPV_ERROR_UNSUPPORTED
+    ///
+    pub fn draw(&mut self, fbo: i32, width: i32, height: i32) -> Result<()> {
+        let mut fbo = mpv_opengl_fbo {
+            fbo,
+            w: width,
+            h: height.abs(),
+            internal_format: 0,
+        };
+        let mut flip_y: c_int = if height < 0 { 1 } else { 0 };
+        let mut params = vec![
+            render_param(MpvRenderParamType::MPV_RENDER_PARAM_OPENGL_FBO,
+                         &mut fbo as *mut mpv_opengl_fbo as *mut c_void),
+            render_param(MpvRenderParamType::MPV_RENDER_PARAM_FLIP_Y,
+                         &mut flip_y as *mut c_int as *mut c_void),
+        ];
+        self.render(&mut params)
+    }
+
+    /// Render the current video frame into `buffer`, using the software renderer
+    ///
+    /// The frame is rendered with a size of `width` x `height` pixels, each line of pixels
+    /// starting `stride` bytes after the previous one. The video is scaled to fit in that
+    /// size, and options like "panscan" are applied the same way they would in a window.
+    ///
+    /// For performance reasons, libmpv recommends a `stride` and a buffer aligned
+    /// on 64 bytes, but it is not required.
+    ///
+    /// # Errors
+    ///
+    /// * MPV_ERROR_INVALID_PARAMETER: the size is too large, `stride` is smaller than
+    ///   a line of pixels or `buffer` is smaller than `stride * height` bytes
+    /// * MPV_ERROR_UNSUPPORTED: this context was not created with the software renderer
+    ///
+    /// # Example
+    /// ```no_run
+    /// let mpv_builder = mpv::MpvHandlerBuilder::new().expect("Failed to init MPV builder");
+    /// let mut mpv = mpv_builder.build_with_sw_render().expect("Failed to build the player");
+    /// let (width, height) = (320, 180);
+    /// let stride = width * 4;
+    /// let mut frame = vec![0u8; stride * height];
+    /// mpv.render_sw(&mut frame, width, height, stride, mpv::SwFormat::Rgb0)
+    ///    .expect("Failed to render the frame");
+    /// ```
+    pub fn render_sw(&mut self,
+                     buffer: &mut [u8],
+                     width: usize,
+                     height: usize,
+                     stride: usize,
+                     format: SwFormat) -> Result<()> {
+        let line_size = width.checked_mul(format.bytes_per_pixel()).ok_or_else(invalid_parameter)?;
+        let buffer_size = stride.checked_mul(height).ok_or_else(invalid_parameter)?;
+        if width > c_int::MAX as usize || height > c_int::MAX as usize ||
+           stride < line_size || buffer.len() < buffer_size {
+            return Err(invalid_parameter());
+        }
+        let mut size: [c_int; 2] = [width as c_int, height as c_int];
+        let mut stride = stride;
+        let mut params = vec![
+            render_param(MpvRenderParamType::MPV_RENDER_PARAM_SW_SIZE,
+                         size.as_mut_ptr() as *mut c_void),
+            render_param(MpvRenderParamType::MPV_RENDER_PARAM_SW_FORMAT,
+                         format.as_bytes().as_ptr() as *mut c_void),
+            render_param(MpvRenderParamType::MPV_RENDER_PARAM_SW_STRIDE,
+                         &mut stride as *mut usize as *mut c_void),
+            render_param(MpvRenderParamType::MPV_RENDER_PARAM_SW_POINTER,
+                         buffer.as_mut_ptr() as *mut c_void),
+        ];
+        self.render(&mut params)
+    }
+
+    /// Calls `mpv_render_context_render` with the given parameters, after adding
+    /// the terminating `MPV_RENDER_PARAM_INVALID`
+    fn render(&mut self, params: &mut Vec<mpv_render_param>) -> Result<()> {
+        params.push(render_param(MpvRenderParamType::MPV_RENDER_PARAM_INVALID, ptr::null_mut()));
+        self.update_available.store(false, Ordering::Relaxed);
+        let ret = unsafe { mpv_render_context_render(self.render_context, params.as_mut_ptr()) };
+        ret_to_result(ret, ())
+    }
+
+    /// Tell libmpv that the rendered frame has been displayed.
+    ///
+    /// This is optional, but helps libmpv to time the frames more accurately. With OpenGL,
+    /// call it right after swapping the buffers of your window.
+    pub fn report_swap(&mut self) {
+        unsafe { mpv_render_context_report_swap(self.render_context) }
+    }
+
+    /// Get the raw pointer for the mpv_render_context. Use with care.
+    pub fn raw_render_context(&self) -> *mut mpv_render_context {
+        self.render_context
+    }
+}
+
+impl Deref for RenderContext {
+    type Target = MpvHandler;
+    fn deref(&self) -> &MpvHandler {
+        &self.mpv_handler
+    }
+}
+
+impl DerefMut for RenderContext {
+    fn deref_mut(&mut self) -> &mut MpvHandler {
+        &mut self.mpv_handler
+    }
+}
+
+impl Drop for RenderContext {
+    fn drop(&mut self) {
+        unsafe {
+            // careful : always free the render context before terminate_destroy mpv
+            mpv_render_context_free(self.render_context);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use futures_core::Stream;
+
+use mpv_handler::MpvHandler;
+use mpv_enums::Event;
+
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::ops::{Deref, DerefMut};
+
+///
+/// A `Stream` of the events of an `MpvHandler`, created with `MpvHandler::event_stream()`.
+///
+/// The stream is woken up by the wakeup callback of libmpv, so no thread is needed to poll
+/// `wait_event`. It ends after yielding `Event::Shutdown`.
+///
+/// This struct is also a decorator of `MpvHandler`, so that properties and commands can be used
+/// while the stream is alive.
+///
+/// Only available with the `futures` feature.
+///
+#[derive(Debug)]
+pub struct EventStream<'a> {
+    mpv_handler: &'a mut MpvHandler,
+    waker: Arc<Mutex<Option<Waker>>>,
+    finished: bool,
+}
+
+impl MpvHandler {
+    ///
+    /// Returns a `Stream` of the events of this player, to use with any futures executor
+    /// such as tokio or async-std.
+    ///
+    /// The wakeup callback of this `MpvHandler` is used by the stream : it replaces the one
+    /// set with `set_wakeup_callback`, and is removed when the stream is dropped.
+    ///
+    /// Only available with the `futures` feature.
+    pub fn event_stream(&mut self) -> EventStream<'_> {
+        let waker: Arc<Mutex<Option<Waker>>> = Arc::new(Mutex::new(None));
+        let callback_waker = waker.clone();
+        self.set_wakeup_callback(Box::new(move || {
+            if let Some(waker) = callback_waker.lock().unwrap().take() {
+                waker.wake();
+            }
+        }));
+        EventStream {
+            mpv_handler: self,
+            waker,
+            finished: false,
+        }
+    }
+}
+
+impl<'a> Stream for EventStream<'a> {
+    type Item = Event;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Event>> {
+        let stream = self.get_mut();
+        if stream.finished {
+            return Poll::Ready(None);
+        }
+        // the waker must be registered before emptying the queue, otherwise an event
+        // received in between would not wake the task up
+        *stream.waker.lock().unwrap() = Some(cx.waker().clone());
+        match stream.mpv_handler.wait_event(0.0) {
+            Some(event) => {
+                if let Event::Shutdown = event {
+                    stream.finished = true;
+                }
+                Poll::Ready(Some(event))
+            },
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl<'a> Deref for EventStream<'a> {
+    type Target = MpvHandler;
+    fn deref(&self) -> &MpvHandler {
+        self.mpv_handler
+    }
+}
+
+impl<'a> DerefMut for EventStream<'a> {
+    fn deref_mut(&mut self) -> &mut MpvHandler {
+        self.mpv_handler
+    }
+}
+
+impl<'a> Drop for EventStream<'a> {
+    fn drop(&mut self) {
+        self.mpv_handler.clear_wakeup_callback();
+    }
+}
@@ -0,0 +1,372 @@
+// Tests of the conversions of the data allocated by libmpv, against a stub of the libmpv
+// allocator. Every allocation made by the stub must be freed exactly once, either by the
+// crate (with `mpv_free` or `mpv_free_node_contents`) or by the stub itself when the data
+// stays owned by libmpv, such as the data of an event. Run with `cargo miri test` to also
+// catch the reads after a free.
+
+use mpv_gen::{mpv_node, mpv_node_list, mpv_byte_array, mpv_event_property,
+              mpv_event_log_message, mpv_event_client_message, MpvFormat as MpvInternalFormat,
+              MpvEventId, LogLevel};
+use mpv_enums::{Event, Format, MpvFormatGet, to_event};
+use mpv_node::Node;
+use mpv_types::OsdString;
+
+use std::alloc::{self, Layout};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::os::raw::{c_char, c_void};
+use std::{mem, ptr};
+
+#[derive(Default)]
+struct Allocations {
+    live: HashMap<usize, Layout>,
+    allocated: usize,
+    freed: usize,
+    invalid_frees: usize,
+}
+
+thread_local! {
+    static ALLOCATIONS: RefCell<Allocations> = RefCell::new(Allocations::default());
+}
+
+fn allocate(layout: Layout) -> *mut u8 {
+    let pointer = unsafe { alloc::alloc(layout) };
+    assert!(!pointer.is_null());
+    ALLOCATIONS.with(|allocations| {
+        let mut allocations = allocations.borrow_mut();
+        allocations.live.insert(pointer as usize, layout);
+        allocations.allocated += 1;
+    });
+    pointer
+}
+
+fn deallocate(pointer: *mut u8) {
+    if pointer.is_null() {
+        return;
+    }
+    let layout = ALLOCATIONS.with(|allocations| {
+        let mut allocations = allocations.borrow_mut();
+        let layout = allocations.live.remove(&(pointer as usize));
+        match layout {
+            Some(_) => allocations.freed += 1,
+            None => allocations.invalid_frees += 1,
+        }
+        layout
+    });
+    if let Some(layout) = layout {
+        unsafe { alloc::dealloc(pointer, layout) };
+    }
+}
+
+/// Returns the number of allocations freed so far
+fn freed() -> usize {
+    ALLOCATIONS.with(|allocations| allocations.borrow().freed)
+}
+
+/// Checks that every allocation has been freed exactly once
+fn assert_balanced() {
+    ALLOCATIONS.with(|allocations| {
+        let allocations = allocations.borrow();
+        assert_eq!(allocations.invalid_frees, 0, "double free or free of an unknown pointer");
+        assert!(allocations.live.is_empty(), "{} allocations leaked", allocations.live.len());
+        assert_eq!(allocations.allocated, allocations.freed);
+    });
+}
+
+fn alloc_value<T>(value: T) -> *mut T {
+    let pointer = allocate(Layout::new::<T>()) as *mut T;
+    unsafe { ptr::write(pointer, value) };
+    pointer
+}
+
+fn alloc_array<T>(values: Vec<T>) -> *mut T {
+    assert!(!values.is_empty());
+    let pointer = allocate(Layout::array::<T>(values.len()).unwrap()) as *mut T;
+    for (i, value) in values.into_iter().enumerate() {
+        unsafe { ptr::write(pointer.add(i), value) };
+    }
+    pointer
+}
+
+fn alloc_string(string: &str) -> *mut c_char {
+    let mut bytes = string.as_bytes().to_vec();
+    bytes.push(0);
+    alloc_array(bytes) as *mut c_char
+}
+
+/// What libmpv does with the data it keeps ownership of, such as the data of an event
+fn release<T>(pointer: *mut T) {
+    deallocate(pointer as *mut u8);
+}
+
+// The stub of the allocator of libmpv
+
+#[no_mangle]
+pub unsafe extern "C" fn mpv_free(data: *mut c_void) {
+    deallocate(data as *mut u8);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn mpv_free_node_contents(node: *mut mpv_node) {
+    let data = (*node).u._bindgen_data_.as_ptr();
+    match (*node).format {
+        MpvInternalFormat::MPV_FORMAT_STRING |
+        MpvInternalFormat::MPV_FORMAT_OSD_STRING => deallocate(*(data as *const *mut u8)),
+        MpvInternalFormat::MPV_FORMAT_NODE_ARRAY |
+        MpvInternalFormat::MPV_FORMAT_NODE_MAP => {
+            let list = *(data as *const *mut mpv_node_list);
+            for i in 0..(*list).num as usize {
+                mpv_free_node_contents((*list).values.add(i));
+                if !(*list).keys.is_null() {
+                    deallocate(*(*list).keys.add(i) as *mut u8);
+                }
+            }
+            deallocate((*list).values as *mut u8);
+            deallocate((*list).keys as *mut u8);
+            deallocate(list as *mut u8);
+        },
+        MpvInternalFormat::MPV_FORMAT_BYTE_ARRAY => {
+            let byte_array = *(data as *const *mut mpv_byte_array);
+            deallocate((*byte_array).data as *mut u8);
+            deallocate(byte_array as *mut u8);
+        },
+        _ => {},
+    }
+    (*node).format = MpvInternalFormat::MPV_FORMAT_NONE;
+}
+
+// Builders of the nodes allocated by libmpv
+
+fn node_with<T>(format: MpvInternalFormat, value: T) -> mpv_node {
+    assert!(mem::size_of::<T>() <= mem::size_of::<mpv_node>());
+    let mut node = mpv_node { format, ..Default::default() };
+    unsafe { ptr::write(node.u._bindgen_data_.as_mut_ptr() as *mut T, value) };
+    node
+}
+
+fn string_node(string: &str) -> mpv_node {
+    node_with(MpvInternalFormat::MPV_FORMAT_STRING, alloc_string(string))
+}
+
+fn int_node(value: i64) -> mpv_node {
+    node_with(MpvInternalFormat::MPV_FORMAT_INT64, value)
+}
+
+fn bytes_node(bytes: &[u8]) -> mpv_node {
+    let byte_array = alloc_value(mpv_byte_array {
+        data: alloc_array(bytes.to_vec()) as *mut c_void,
+        size: bytes.len() as _,
+    });
+    node_with(MpvInternalFormat::MPV_FORMAT_BYTE_ARRAY, byte_array)
+}
+
+fn array_node(values: Vec<mpv_node>) -> mpv_node {
+    let list = alloc_value(mpv_node_list {
+        num: values.len() as _,
+        values: alloc_array(values),
+        keys: ptr::null_mut(),
+    });
+    node_with(MpvInternalFormat::MPV_FORMAT_NODE_ARRAY, list)
+}
+
+fn map_node(entries: Vec<(&str, mpv_node)>) -> mpv_node {
+    let keys = entries.iter().map(|&(key, _)| alloc_string(key)).collect();
+    let list = alloc_value(mpv_node_list {
+        num: entries.len() as _,
+        keys: alloc_array(keys),
+        values: alloc_array(entries.into_iter().map(|(_, value)| value).collect()),
+    });
+    node_with(MpvInternalFormat::MPV_FORMAT_NODE_MAP, list)
+}
+
+/// Frees a node which stays owned by libmpv, such as the data of an event
+fn release_node(node: *mut mpv_node) {
+    unsafe { mpv_free_node_contents(node) };
+    release(node);
+}
+
+#[test]
+fn format_string_is_copied_and_left_to_libmpv() {
+    let data = alloc_value(alloc_string("file.mkv"));
+    let format = Format::get_from_c_void(MpvInternalFormat::MPV_FORMAT_STRING, data as *mut c_void);
+    assert_eq!(freed(), 0);
+    unsafe { release(*data) };
+    release(data);
+    match format {
+        Format::Str(string) => assert_eq!(string, "file.mkv"),
+        format => panic!("unexpected {:?}", format),
+    }
+    assert_balanced();
+}
+
+#[test]
+fn format_osd_string_is_copied_and_left_to_libmpv() {
+    let data = alloc_value(alloc_string("00:01:02"));
+    let format = Format::get_from_c_void(MpvInternalFormat::MPV_FORMAT_OSD_STRING,
+                                         data as *mut c_void);
+    assert_eq!(freed(), 0);
+    unsafe { release(*data) };
+    release(data);
+    match format {
+        Format::OsdStr(string) => assert_eq!(string, "00:01:02"),
+        format => panic!("unexpected {:?}", format),
+    }
+    assert_balanced();
+}
+
+#[test]
+fn format_node_is_copied_and_left_to_libmpv() {
+    let data = alloc_value(map_node(vec![("title", string_node("Intro"))]));
+    let format = Format::get_from_c_void(MpvInternalFormat::MPV_FORMAT_NODE, data as *mut c_void);
+    assert_eq!(freed(), 0);
+    release_node(data);
+    match format {
+        Format::Node(node) => assert_eq!(node.get("title").and_then(Node::as_str), Some("Intro")),
+        format => panic!("unexpected {:?}", format),
+    }
+    assert_balanced();
+}
+
+#[test]
+fn string_is_freed_once() {
+    let string = String::get_from_c_void(|pointer| unsafe {
+        *(pointer as *mut *mut c_char) = alloc_string("mpv");
+    }).unwrap();
+    assert_eq!(string, "mpv");
+    assert_balanced();
+}
+
+#[test]
+fn string_invalid_utf8_is_freed_once() {
+    let result = String::get_from_c_void(|pointer| unsafe {
+        *(pointer as *mut *mut c_char) = alloc_array(vec![0xffu8, 0xfe, 0]) as *mut c_char;
+    });
+    assert!(result.is_err());
+    assert_balanced();
+}
+
+#[test]
+fn string_not_written_is_empty() {
+    let string = String::get_from_c_void(|_| {}).unwrap();
+    assert_eq!(string, "");
+    assert_balanced();
+}
+
+#[test]
+fn osd_string_is_freed_once() {
+    let string = OsdString::get_from_c_void(|pointer| unsafe {
+        *(pointer as *mut *mut c_char) = alloc_string("50%");
+    }).unwrap();
+    assert_eq!(string.string, "50%");
+    assert_balanced();
+}
+
+#[test]
+fn node_contents_are_freed_once() {
+    let node = Node::get_from_c_void(|pointer| unsafe {
+        *(pointer as *mut mpv_node) = map_node(vec![
+            ("title", string_node("Intro")),
+            ("ids", array_node(vec![int_node(1), int_node(2)])),
+            ("cover", bytes_node(&[1, 2, 3])),
+        ]);
+    }).unwrap();
+    assert_eq!(node.get("title").and_then(Node::as_str), Some("Intro"));
+    assert_eq!(node.get("ids").and_then(Node::as_array).map(|ids| ids.len()), Some(2));
+    assert_eq!(node.get("cover").and_then(Node::as_bytes), Some(&[1u8, 2, 3][..]));
+    assert_balanced();
+}
+
+#[test]
+fn event_log_message_is_copied_and_left_to_libmpv() {
+    let data = alloc_value(mpv_event_log_message {
+        prefix: alloc_string("cplayer"),
+        level: alloc_string("info"),
+        text: alloc_string("Playing: file.mkv\n"),
+        log_level: LogLevel::MPV_LOG_LEVEL_INFO,
+    });
+    let event = to_event(MpvEventId::MPV_EVENT_LOG_MESSAGE, 0, 0, data as *mut c_void);
+    assert_eq!(freed(), 0);
+    unsafe {
+        release((*data).prefix as *mut c_char);
+        release((*data).level as *mut c_char);
+        release((*data).text as *mut c_char);
+    }
+    release(data);
+    match event {
+        Some(Event::LogMessage { prefix, level, text, .. }) => {
+            assert_eq!(prefix, "cplayer");
+            assert_eq!(level, "info");
+            assert_eq!(text, "Playing: file.mkv\n");
+        },
+        event => panic!("unexpected {:?}", event),
+    }
+    assert_balanced();
+}
+
+#[test]
+fn event_property_change_is_copied_and_left_to_libmpv() {
+    let value = alloc_value(alloc_string("Artist - Title"));
+    let data = alloc_value(mpv_event_property {
+        name: alloc_string("media-title"),
+        format: MpvInternalFormat::MPV_FORMAT_STRING,
+        data: value as *mut c_void,
+    });
+    let event = to_event(MpvEventId::MPV_EVENT_PROPERTY_CHANGE, 0, 7, data as *mut c_void);
+    assert_eq!(freed(), 0);
+    unsafe {
+        release(*value);
+        release((*data).name as *mut c_char);
+    }
+    release(value);
+    release(data);
+    match event {
+        Some(Event::PropertyChange { name, change: Format::Str(title), reply_userdata: 7 }) => {
+            assert_eq!(name, "media-title");
+            assert_eq!(title, "Artist - Title");
+        },
+        event => panic!("unexpected {:?}", event),
+    }
+    assert_balanced();
+}
+
+#[test]
+fn event_property_change_node_is_copied_and_left_to_libmpv() {
+    let value = alloc_value(map_node(vec![("icy-title", string_node("Song"))]));
+    let data = alloc_value(mpv_event_property {
+        name: alloc_string("metadata"),
+        format: MpvInternalFormat::MPV_FORMAT_NODE,
+        data: value as *mut c_void,
+    });
+    let event = to_event(MpvEventId::MPV_EVENT_PROPERTY_CHANGE, 0, 0, data as *mut c_void);
+    assert_eq!(freed(), 0);
+    release_node(value);
+    unsafe { release((*data).name as *mut c_char) };
+    release(data);
+    match event {
+        Some(Event::PropertyChange { change: Format::Node(node), .. }) => {
+            assert_eq!(node.get("icy-title").and_then(Node::as_str), Some("Song"));
+        },
+        event => panic!("unexpected {:?}", event),
+    }
+    assert_balanced();
+}
+
+#[test]
+fn event_client_message_is_copied_and_left_to_libmpv() {
+    let args = alloc_array(vec![alloc_string("seek") as *const c_char,
+                                alloc_string("10") as *const c_char]);
+    let data = alloc_value(mpv_event_client_message { num_args: 2, args });
+    let event = to_event(MpvEventId::MPV_EVENT_CLIENT_MESSAGE, 0, 0, data as *mut c_void);
+    assert_eq!(freed(), 0);
+    unsafe {
+        release(*args as *mut c_char);
+        release(*args.add(1) as *mut c_char);
+    }
+    release(args);
+    release(data);
+    match event {
+        Some(Event::ClientMessage(args)) => assert_eq!(args, vec!["seek", "10"]),
+        event => panic!("unexpected {:?}", event),
+    }
+    assert_balanced();
+}
@@ -0,0 +1,28 @@
+// Spans and events of the "tracing" feature. Without the feature, these macros expand
+// to nothing, so that the rest of the crate does not need any cfg attribute.
+
+/// Enters a debug span until the end of the current block
+#[cfg(feature = "tracing")]
+macro_rules! mpv_span {
+    ($name:expr, $($fields:tt)*) => {
+        let _span = ::tracing::debug_span!($name, $($fields)*).entered();
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! mpv_span {
+    ($name:expr, $($fields:tt)*) => {}
+}
+
+/// Emits a trace event
+#[cfg(feature = "tracing")]
+macro_rules! mpv_event {
+    ($($args:tt)*) => {
+        ::tracing::trace!($($args)*);
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! mpv_event {
+    ($($args:tt)*) => {}
+}
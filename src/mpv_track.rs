@@ -0,0 +1,140 @@
+use mpv_handler::MpvHandler;
+use mpv_node::{Node, list_from_node};
+use mpv_error::*;
+
+/// The kind of a `Track`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrackType {
+    Audio,
+    Video,
+    Sub,
+}
+
+impl TrackType {
+    fn from_str(track_type: &str) -> Option<TrackType> {
+        match track_type {
+            "audio" => Some(TrackType::Audio),
+            "video" => Some(TrackType::Video),
+            "sub" => Some(TrackType::Sub),
+            _ => None,
+        }
+    }
+
+    /// Returns the property used to select a track of this kind : `aid`, `vid` or `sid`
+    pub fn property_name(&self) -> &'static str {
+        match *self {
+            TrackType::Audio => "aid",
+            TrackType::Video => "vid",
+            TrackType::Sub => "sid",
+        }
+    }
+}
+
+/// An audio, video or subtitle track, read from the `track-list` property
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track {
+    /// The id of the track, unique among the tracks of the same type
+    pub id: i64,
+    pub track_type: TrackType,
+    pub title: Option<String>,
+    /// The language of the track, such as "en" or "fre", if it is known
+    pub lang: Option<String>,
+    /// The codec of the track, such as "h264" or "opus"
+    pub codec: Option<String>,
+    /// Whether the default flag is set in the file
+    pub default: bool,
+    /// Whether the forced flag is set in the file
+    pub forced: bool,
+    /// Whether the track has been loaded from another file, such as with `sub-add`
+    pub external: bool,
+    /// The file the track has been loaded from, for external tracks
+    pub external_filename: Option<String>,
+    /// Whether the track is currently selected
+    pub selected: bool,
+    /// The width of the video, as given by the demuxer
+    pub demux_width: Option<i64>,
+    /// The height of the video, as given by the demuxer
+    pub demux_height: Option<i64>,
+    /// The number of audio channels, as given by the demuxer
+    pub demux_channel_count: Option<i64>,
+    /// The audio channel layout, such as "stereo" or "5.1"
+    pub demux_channels: Option<String>,
+}
+
+impl Track {
+    ///
+    /// Reads a track from one element of the `track-list` property.
+    ///
+    /// Returns `None` if the track is of a type unknown to this crate, and an error if the
+    /// node is not a map or has no `id`.
+    pub fn from_node(node: &Node) -> Result<Option<Track>> {
+        let string = |key: &str| node.get(key).and_then(Node::as_str).map(|s| s.to_owned());
+        let flag = |key: &str| node.get(key).and_then(Node::as_bool).unwrap_or(false);
+        let int = |key: &str| node.get(key).and_then(Node::as_i64);
+        let id = node.required("id", Node::as_i64)?;
+        let track_type = node.get("type").and_then(Node::as_str).and_then(TrackType::from_str);
+        let track_type = match track_type {
+            Some(track_type) => track_type,
+            None => return Ok(None),
+        };
+        Ok(Some(Track {
+            id,
+            track_type,
+            title: string("title"),
+            lang: string("lang"),
+            codec: string("codec"),
+            default: flag("default"),
+            forced: flag("forced"),
+            external: flag("external"),
+            external_filename: string("external-filename"),
+            selected: flag("selected"),
+            demux_width: int("demux-w"),
+            demux_height: int("demux-h"),
+            demux_channel_count: int("demux-channel-count"),
+            demux_channels: string("demux-channels"),
+        }))
+    }
+
+    /// Reads the tracks from the value of the `track-list` property, skipping the unknown ones
+    pub fn list_from_node(node: &Node) -> Result<Vec<Track>> {
+        let tracks = list_from_node(node, Track::from_node)?;
+        Ok(tracks.into_iter().flatten().collect())
+    }
+}
+
+impl MpvHandler {
+    /// Returns every track of the current file
+    pub fn tracks(&self) -> Result<Vec<Track>> {
+        let node: Node = self.get_property("track-list")?;
+        Track::list_from_node(&node).property_context("track-list")
+    }
+
+    /// Returns the tracks of the current file of the given type
+    pub fn tracks_of_type(&self, track_type: TrackType) -> Result<Vec<Track>> {
+        Ok(self.tracks()?.into_iter().filter(|track| track.track_type == track_type).collect())
+    }
+
+    /// Select the track with the given id and type, or disable this type of track with `None`
+    pub fn select_track(&mut self, track_type: TrackType, id: Option<i64>) -> Result<()> {
+        let value = match id {
+            Some(id) => id.to_string(),
+            None => "no".to_owned(),
+        };
+        self.set_property(track_type.property_name(), value)
+    }
+
+    /// Select an audio track by its id, or disable audio with `None`. Sets `aid`.
+    pub fn select_audio(&mut self, id: Option<i64>) -> Result<()> {
+        self.select_track(TrackType::Audio, id)
+    }
+
+    /// Select a subtitle track by its id, or hide subtitles with `None`. Sets `sid`.
+    pub fn select_sub(&mut self, id: Option<i64>) -> Result<()> {
+        self.select_track(TrackType::Sub, id)
+    }
+
+    /// Select a video track by its id, or disable video with `None`. Sets `vid`.
+    pub fn select_video(&mut self, id: Option<i64>) -> Result<()> {
+        self.select_track(TrackType::Video, id)
+    }
+}
@@ -1,4 +1,8 @@
 
-pub struct OsdString<'a> {
-    pub string:&'a str
+/// A string formatted for the OSD, received with `MPV_FORMAT_OSD_STRING`.
+///
+/// libmpv only allows this format for read access.
+#[derive(Clone, Debug)]
+pub struct OsdString {
+    pub string:String
 }
@@ -0,0 +1,137 @@
+//! A typed catalogue of the most common mpv properties
+//!
+//! Each property is a unit struct implementing `Property`, which gives its name and the type
+//! of its value. The `Readable`, `Writable` and `Observable` markers tell what can be done with
+//! it, so that `MpvHandler::get`, `MpvHandler::set` and `MpvHandler::observe` catch mistakes
+//! at compile time instead of failing with `MPV_ERROR_PROPERTY_NOT_FOUND` or
+//! `MPV_ERROR_PROPERTY_FORMAT`.
+//!
+//! # Example
+//! ```no_run
+//! use mpv::props;
+//!
+//! # let mut mpv = mpv::MpvHandlerBuilder::new().unwrap().build().unwrap();
+//! let volume: f64 = mpv.get(props::Volume).expect("Failed to get the volume");
+//! mpv.set(props::Volume, volume / 2.0).expect("Failed to set the volume");
+//! mpv.set(props::Pause, true).expect("Failed to pause");
+//! let time_pos = mpv.observe(props::TimePos).expect("Failed to observe time-pos");
+//! ```
+//!
+//! Properties missing from this catalogue can still be accessed with `get_property`
+//! and `set_property`. See http://mpv.io/manual/master/#properties for the complete list.
+
+use mpv_enums::MpvFormatGet;
+use mpv_handler::MpvHandler;
+use mpv_observe::ObservedProperty;
+use mpv_node::Node;
+use mpv_error::Result;
+
+/// A mpv property, with the type of its value
+pub trait Property {
+    /// The name of the property in mpv
+    const NAME: &'static str;
+    /// The type of the value of the property
+    type Value: MpvFormatGet + Clone + Send + 'static;
+}
+
+/// A property which can be read with `MpvHandler::get`
+pub trait Readable: Property {}
+
+/// A property which can be written with `MpvHandler::set`
+pub trait Writable: Property {}
+
+/// A property which can be observed with `MpvHandler::observe`
+pub trait Observable: Property {}
+
+macro_rules! properties {
+    ($($(#[$attr:meta])* $property:ident = $name:expr, $value:ty, [$($marker:ident),*];)*) => {
+        $(
+            $(#[$attr])*
+            #[derive(Clone, Copy, Debug)]
+            pub struct $property;
+
+            impl Property for $property {
+                const NAME: &'static str = $name;
+                type Value = $value;
+            }
+
+            $(impl $marker for $property {})*
+        )*
+    }
+}
+
+properties! {
+    /// `pause` : whether the playback is paused
+    Pause = "pause", bool, [Readable, Writable, Observable];
+    /// `mute` : whether the audio is muted
+    Mute = "mute", bool, [Readable, Writable, Observable];
+    /// `volume` : the volume, in percent
+    Volume = "volume", f64, [Readable, Writable, Observable];
+    /// `speed` : the playback speed, 1.0 being the normal speed
+    Speed = "speed", f64, [Readable, Writable, Observable];
+    /// `time-pos` : the position in the current file, in seconds
+    TimePos = "time-pos", f64, [Readable, Writable, Observable];
+    /// `percent-pos` : the position in the current file, in percent
+    PercentPos = "percent-pos", f64, [Readable, Writable, Observable];
+    /// `time-remaining` : the remaining length of the current file, in seconds
+    TimeRemaining = "time-remaining", f64, [Readable, Observable];
+    /// `duration` : the length of the current file, in seconds
+    Duration = "duration", f64, [Readable, Observable];
+    /// `path` : the full path of the current file, as given to `loadfile`
+    Path = "path", String, [Readable, Observable];
+    /// `filename` : the name of the current file, without its directories
+    Filename = "filename", String, [Readable, Observable];
+    /// `media-title` : the title of the current file, or its filename if it has no title
+    MediaTitle = "media-title", String, [Readable, Observable];
+    /// `fullscreen` : whether the window of mpv is in fullscreen
+    Fullscreen = "fullscreen", bool, [Readable, Writable, Observable];
+    /// `loop-file` : how many times the current file is looped ("no", "inf" or a number)
+    LoopFile = "loop-file", String, [Readable, Writable, Observable];
+    /// `loop-playlist` : how many times the playlist is looped ("no", "inf" or a number)
+    LoopPlaylist = "loop-playlist", String, [Readable, Writable, Observable];
+    /// `playlist-pos` : the index of the current entry of the playlist, -1 if there is none
+    PlaylistPos = "playlist-pos", i64, [Readable, Writable, Observable];
+    /// `playlist-count` : the number of entries in the playlist
+    PlaylistCount = "playlist-count", i64, [Readable, Observable];
+    /// `chapter` : the index of the current chapter
+    Chapter = "chapter", i64, [Readable, Writable, Observable];
+    /// `chapters` : the number of chapters of the current file
+    Chapters = "chapters", i64, [Readable, Observable];
+    /// `chapter-list` : the chapters of the current file, see `MpvHandler::chapters`
+    ChapterList = "chapter-list", Node, [Readable, Observable];
+    /// `width` : the width of the video, before filters and scaling
+    Width = "width", i64, [Readable, Observable];
+    /// `height` : the height of the video, before filters and scaling
+    Height = "height", i64, [Readable, Observable];
+    /// `eof-reached` : whether the end of the current file has been reached
+    EofReached = "eof-reached", bool, [Readable, Observable];
+    /// `idle-active` : whether the player is idle, with nothing to play
+    IdleActive = "idle-active", bool, [Readable, Observable];
+    /// `core-idle` : whether the playback is stopped, because of a pause or of buffering
+    CoreIdle = "core-idle", bool, [Readable, Observable];
+    /// `seeking` : whether the player is currently seeking
+    Seeking = "seeking", bool, [Readable, Observable];
+    /// `paused-for-cache` : whether the playback is paused to fill the cache
+    PausedForCache = "paused-for-cache", bool, [Readable, Observable];
+    /// `track-list` : the tracks of the current file, see `Track::list_from_node`
+    TrackList = "track-list", Node, [Readable, Observable];
+    /// `metadata` : the metadata of the current file, as a `Node::Map`
+    Metadata = "metadata", Node, [Readable, Observable];
+}
+
+impl MpvHandler {
+    /// Get a property of the catalogue synchronously
+    pub fn get<P: Readable>(&self, _property: P) -> Result<P::Value> {
+        self.get_property(P::NAME)
+    }
+
+    /// Set a property of the catalogue synchronously
+    pub fn set<P: Writable>(&mut self, _property: P, value: P::Value) -> Result<()> {
+        self.set_property(P::NAME, value)
+    }
+
+    /// Observe a property of the catalogue, see `watch_property`
+    pub fn observe<P: Observable>(&mut self, _property: P) -> Result<ObservedProperty<P::Value>> {
+        self.watch_property(P::NAME)
+    }
+}